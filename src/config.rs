@@ -1,4 +1,15 @@
+use crate::graphql::GraphqlOperation;
+use crate::grpc::GrpcCall;
+use crate::http_client::HttpClientConfig;
 use crate::models::Scenario;
+use crate::request_log::RequestLogConfig;
+use crate::sinks::MetricsSinkConfig;
+use crate::sse::EventStream;
+use crate::trace_context::DEFAULT_SLOWEST_REQUESTS;
+use crate::websocket::WebSocketScenario;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
 
 pub struct LoadTestConfig {
     pub base_url: String,
@@ -8,6 +19,23 @@ pub struct LoadTestConfig {
     pub period_id: String,
     pub cycle_id: String,
     pub scenarios: Vec<Scenario>,
+    pub time_series_interval: Duration, // Bucket width for per-scenario time series
+    pub record_requests: Option<PathBuf>, // Stream one row per request to this CSV or .parquet file (and read response bodies to size them)
+    pub request_log: Option<RequestLogConfig>, // Write sampled requests to a JSONL log
+    pub metrics_sinks: Vec<MetricsSinkConfig>, // Where per-interval aggregates are pushed
+    pub metrics_push_interval: Duration, // How often aggregates are pushed to the sinks
+    pub run_id: Option<String>, // Tag for pushed metrics; defaults to the start time, like history run IDs
+    pub trace_headers: bool, // Send a W3C `traceparent` header with every request
+    pub request_id_header: Option<String>, // Also send a random UUID in this header, e.g. `X-Request-ID`
+    pub slowest_requests: usize, // Slowest requests kept per endpoint for the report
+    pub http_client: HttpClientConfig,
+    pub endpoint_http_clients: HashMap<String, HttpClientConfig>, // Per-endpoint overrides, keyed by endpoint path
+    pub cookie_jar: bool, // Give each virtual user a cookie jar kept across the scenario's endpoints
+    pub seed_cookies: Vec<String>, // `Set-Cookie` style values put in every jar at the start of a scenario
+    pub graphql_operations: Vec<GraphqlOperation>, // Sent after the GET endpoints of each scenario, one result row each
+    pub grpc_calls: Vec<GrpcCall>, // Sent after the GraphQL operations of each scenario, one result row each
+    pub websockets: Vec<WebSocketScenario>, // Run after the HTTP endpoints of each scenario, one result row each
+    pub event_streams: Vec<EventStream>, // Subscribed to after the WebSockets of each scenario, one result row each
 }
 
 impl LoadTestConfig {
@@ -20,6 +48,23 @@ impl LoadTestConfig {
             period_id: "5b39887b-6659-4d87-975f-508917131ea3".to_string(),
             cycle_id: "a915cb6e-0974-4fb8-9553-81f04ce7ca45".to_string(),
             scenarios: Self::generate_scaling_scenarios(),
+            time_series_interval: Duration::from_secs(1),
+            record_requests: None,
+            request_log: None,
+            metrics_sinks: Vec::new(),
+            metrics_push_interval: Duration::from_secs(10),
+            run_id: None,
+            trace_headers: true,
+            request_id_header: None,
+            slowest_requests: DEFAULT_SLOWEST_REQUESTS,
+            http_client: HttpClientConfig::default(),
+            endpoint_http_clients: HashMap::new(),
+            cookie_jar: false,
+            seed_cookies: Vec::new(),
+            graphql_operations: Vec::new(),
+            grpc_calls: Vec::new(),
+            websockets: Vec::new(),
+            event_streams: Vec::new(),
         }
     }
 
@@ -0,0 +1,105 @@
+use reqwest::Url;
+use reqwest::blocking::Response;
+use reqwest::cookie::{CookieStore, Jar};
+use reqwest::header::{HeaderValue, SET_COOKIE};
+use std::sync::Arc;
+
+/// One cookie jar per virtual user, kept for a whole scenario.
+///
+/// Cookies are sent and stored around each request rather than through the client, so jars stay
+/// per user under every connection model. Cookies set on intermediate redirect responses are not kept.
+#[derive(Clone)]
+pub(crate) struct CookieJars {
+    jars: Arc<Vec<Jar>>,
+}
+
+impl CookieJars {
+    /// `seed` holds `Set-Cookie` style values (`name=value; Domain=...; Path=...`) stored as if `base_url` sent them
+    pub(crate) fn new(virtual_users: usize, seed: &[String], base_url: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let url = Url::parse(base_url)?;
+        let jars = (0..virtual_users.max(1))
+            .map(|_| {
+                let jar = Jar::default();
+                for cookie in seed {
+                    jar.add_cookie_str(cookie, &url);
+                }
+                jar
+            })
+            .collect();
+        Ok(Self { jars: Arc::new(jars) })
+    }
+
+    /// `Cookie` header for the `request_index`-th request; virtual users take requests in turn
+    pub(crate) fn cookie_header(&self, request_index: usize, url: &Url) -> Option<HeaderValue> {
+        self.jar(request_index).cookies(url)
+    }
+
+    /// Keep the cookies `response` sets for the user that sent it
+    pub(crate) fn store(&self, request_index: usize, response: &Response) {
+        let mut cookies = response.headers().get_all(SET_COOKIE).iter();
+        self.jar(request_index).set_cookies(&mut cookies, response.url());
+    }
+
+    /// Cookies each user would send to `url`
+    pub(crate) fn cookie_counts(&self, url: &Url) -> Vec<usize> {
+        self.jars.iter()
+            .map(|jar| match jar.cookies(url) {
+                Some(header) => header.to_str().map(|cookies| cookies.split("; ").count()).unwrap_or(0),
+                None => 0,
+            })
+            .collect()
+    }
+
+    fn jar(&self, request_index: usize) -> &Jar {
+        &self.jars[request_index % self.jars.len()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn seeded_cookies_go_to_every_user() {
+        let seed = ["session=abc".to_string(), "theme=dark; Path=/app".to_string()];
+        let jars = CookieJars::new(3, &seed, "http://api.test").unwrap();
+        let app = Url::parse("http://api.test/app/home").unwrap();
+        let other = Url::parse("http://other.test/").unwrap();
+
+        assert_eq!(jars.cookie_counts(&app), vec![2, 2, 2]);
+        assert_eq!(jars.cookie_header(1, &Url::parse("http://api.test/").unwrap()).unwrap(), "session=abc");
+        assert!(jars.cookie_header(1, &other).is_none());
+        assert_eq!(CookieJars::new(0, &[], "http://api.test").unwrap().cookie_counts(&app), vec![0]);
+        assert!(CookieJars::new(1, &[], "not a url").is_err());
+    }
+
+    #[test]
+    fn stored_cookies_stay_with_the_user_that_got_them() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/login", listener.local_addr().unwrap())).unwrap();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = [0; 1024];
+            let _ = stream.read(&mut buffer).unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nSet-Cookie: token=u1\r\nSet-Cookie: lang=en\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+        });
+
+        let jars = CookieJars::new(2, &[], url.as_str()).unwrap();
+        let response = reqwest::blocking::get(url.clone()).unwrap();
+        // Request 3 is virtual user 1's second request
+        jars.store(3, &response);
+        server.join().unwrap();
+
+        assert_eq!(jars.cookie_counts(&url), vec![0, 2]);
+        let header = jars.cookie_header(1, &url).unwrap();
+        let mut cookies: Vec<&str> = header.to_str().unwrap().split("; ").collect();
+        cookies.sort();
+        assert_eq!(cookies, vec!["lang=en", "token=u1"]);
+        assert!(jars.cookie_header(0, &url).is_none());
+    }
+}
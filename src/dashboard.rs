@@ -0,0 +1,212 @@
+use crate::live::{LiveMonitor, LiveSnapshot, RunStage};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Gauge, Paragraph, Sparkline};
+use ratatui::Frame;
+use std::io;
+use std::time::Duration;
+
+const REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Full-screen terminal dashboard for a running load test.
+///
+/// Draws until `is_done` returns true. Keys: `p` pause/resume, `s` skip the
+/// current scenario, `q`/Esc/Ctrl-C abort (the run finishes with the results
+/// collected so far).
+pub fn run_dashboard<F: Fn() -> bool>(monitor: &LiveMonitor, is_done: F) -> io::Result<()> {
+    let mut terminal = ratatui::init();
+    let result = (|| -> io::Result<()> {
+        while !is_done() {
+            let snapshot = monitor.snapshot();
+            terminal.draw(|frame| draw(frame, &snapshot))?;
+
+            if event::poll(REFRESH_INTERVAL)?
+                && let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+            {
+                match key.code {
+                    KeyCode::Char('p') => monitor.control().toggle_pause(),
+                    KeyCode::Char('s') => monitor.control().skip_scenario(),
+                    KeyCode::Char('q') | KeyCode::Esc => monitor.control().abort(),
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        monitor.control().abort()
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    })();
+    ratatui::restore();
+    result
+}
+
+fn draw(frame: &mut Frame, snapshot: &LiveSnapshot) {
+    let [header, progress, stats, endpoints, footer] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Length(3),
+        Constraint::Length(7),
+        Constraint::Min(3),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    draw_header(frame, header, snapshot);
+    draw_progress(frame, progress, snapshot);
+    draw_stats(frame, stats, snapshot);
+    draw_endpoints(frame, endpoints, snapshot);
+
+    frame.render_widget(
+        Paragraph::new("p: pause/resume   s: skip scenario   q: abort").style(Style::default().fg(Color::DarkGray)),
+        footer,
+    );
+}
+
+fn draw_header(frame: &mut Frame, area: Rect, snapshot: &LiveSnapshot) {
+    let (stage, color) = match snapshot.stage {
+        RunStage::Starting => ("STARTING", Color::Blue),
+        RunStage::Running => ("RUNNING", Color::Green),
+        RunStage::Paused => ("PAUSED", Color::Yellow),
+        RunStage::Skipping => ("SKIPPING", Color::Yellow),
+        RunStage::Aborting => ("ABORTING", Color::Red),
+        RunStage::Finished => ("FINISHED", Color::Blue),
+    };
+
+    let line = Line::from(vec![
+        Span::styled(format!(" {} ", stage), Style::default().fg(Color::Black).bg(color).add_modifier(Modifier::BOLD)),
+        Span::raw(format!(
+            "  Scenario {}/{}  |  Concurrency {}  |  Endpoint {}",
+            snapshot.scenario_index, snapshot.scenario_count, snapshot.concurrency, snapshot.current_endpoint
+        )),
+    ]);
+    frame.render_widget(
+        Paragraph::new(line).block(Block::default().borders(Borders::ALL).title("Load Test")),
+        area,
+    );
+}
+
+fn draw_progress(frame: &mut Frame, area: Rect, snapshot: &LiveSnapshot) {
+    let ratio = if snapshot.planned_requests > 0 {
+        (snapshot.completed_requests as f64 / snapshot.planned_requests as f64).min(1.0)
+    } else {
+        0.0
+    };
+    let remaining = snapshot.remaining_seconds
+        .map(format_duration)
+        .unwrap_or_else(|| "-".to_string());
+
+    frame.render_widget(
+        Gauge::default()
+            .block(Block::default().borders(Borders::ALL).title("Progress"))
+            .gauge_style(Style::default().fg(Color::Cyan))
+            .ratio(ratio)
+            .label(format!(
+                "{}/{} requests  |  elapsed {}  |  remaining ~{}",
+                snapshot.completed_requests,
+                snapshot.planned_requests,
+                format_duration(snapshot.elapsed_seconds),
+                remaining
+            )),
+        area,
+    );
+}
+
+fn draw_stats(frame: &mut Frame, area: Rect, snapshot: &LiveSnapshot) {
+    let [throughput, errors] = Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(area);
+
+    let lines = vec![
+        Line::from(format!("RPS (rolling):   {:.2}", snapshot.rolling_rps)),
+        Line::from(format!("In flight:       {}", snapshot.in_flight)),
+        Line::from(format!(
+            "Latency p50/p95/p99: {}ms / {}ms / {}ms",
+            snapshot.rolling_p50_latency, snapshot.rolling_p95_latency, snapshot.rolling_p99_latency
+        )),
+        Line::from(format!("Failed requests: {}", snapshot.failed_requests)),
+    ];
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Throughput & Latency")),
+        throughput,
+    );
+
+    let error_lines: Vec<Line> = if snapshot.errors_by_class.is_empty() {
+        vec![Line::from("No errors")]
+    } else {
+        snapshot.errors_by_class.iter()
+            .map(|(class, count)| Line::from(format!("{:<14} {}", class, count)))
+            .collect()
+    };
+    frame.render_widget(
+        Paragraph::new(error_lines)
+            .style(Style::default().fg(if snapshot.failed_requests > 0 { Color::Red } else { Color::Reset }))
+            .block(Block::default().borders(Borders::ALL).title("Errors by Class")),
+        errors,
+    );
+}
+
+fn draw_endpoints(frame: &mut Frame, area: Rect, snapshot: &LiveSnapshot) {
+    let block = Block::default().borders(Borders::ALL).title("Endpoints (requests/sec)");
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let rows = Layout::vertical(vec![Constraint::Length(1); inner.height as usize]).split(inner);
+    for (endpoint, row) in snapshot.endpoints.iter().zip(rows.iter()) {
+        let [label, sparkline] = Layout::horizontal([Constraint::Length(60), Constraint::Min(10)]).areas(*row);
+        let style = if endpoint.endpoint == snapshot.current_endpoint {
+            Style::default().add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+
+        frame.render_widget(
+            Paragraph::new(format!(
+                "{:<44} {:>6} {:>5}err",
+                truncate(&endpoint.endpoint, 44),
+                endpoint.completed,
+                endpoint.errors
+            ))
+            .style(style),
+            label,
+        );
+        frame.render_widget(
+            Sparkline::default()
+                .data(&endpoint.per_second)
+                .style(Style::default().fg(Color::Green)),
+            sparkline,
+        );
+    }
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        text.to_string()
+    } else {
+        let tail: String = text.chars().rev().take(max - 1).collect::<Vec<_>>().into_iter().rev().collect();
+        format!("…{}", tail)
+    }
+}
+
+fn format_duration(seconds: f64) -> String {
+    let total = seconds.max(0.0) as u64;
+    format!("{:02}:{:02}:{:02}", total / 3600, (total % 3600) / 60, total % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_keeps_the_tail_of_long_names() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("/api/v1/users/profile", 8), "…profile");
+        assert_eq!(truncate("/api/v1/users/profile", 8).chars().count(), 8);
+    }
+
+    #[test]
+    fn format_duration_is_clock_style() {
+        assert_eq!(format_duration(0.0), "00:00:00");
+        assert_eq!(format_duration(3725.9), "01:02:05");
+        assert_eq!(format_duration(-5.0), "00:00:00");
+    }
+}
@@ -0,0 +1,400 @@
+use crate::models::{LoadTestReport, RequestRecord};
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_CSV_DIR: &str = "load_test_csv";
+
+#[derive(Serialize)]
+struct ScenarioRow {
+    scenario: usize,
+    concurrency: usize,
+    total_requests: usize,
+    successful_requests: usize,
+    failed_requests: usize,
+    success_rate: f64,
+    rps: f64,
+    mean_latency_ms: f64,
+    p95_latency_ms: u64,
+    p99_latency_ms: u64,
+    duration_seconds: f64,
+}
+
+#[derive(Serialize)]
+struct EndpointRow<'a> {
+    scenario: usize,
+    concurrency: usize,
+    endpoint: &'a str,
+    total_requests: usize,
+    successful_requests: usize,
+    failed_requests: usize,
+    success_rate: f64,
+    rps: f64,
+    mean_latency_ms: f64,
+    p95_latency_ms: u64,
+    p99_latency_ms: u64,
+    duration_seconds: f64,
+}
+
+#[derive(Serialize)]
+struct StatusCodeRow<'a> {
+    scenario: usize,
+    concurrency: usize,
+    endpoint: &'a str,
+    status: u16, // 0 when no response was received
+    count: usize,
+}
+
+#[derive(Serialize)]
+struct GrpcStatusCodeRow<'a> {
+    scenario: usize,
+    concurrency: usize,
+    endpoint: &'a str,
+    grpc_code: u16, // 0 = OK
+    count: usize,
+}
+
+#[derive(Serialize)]
+struct TimeSeriesRow {
+    scenario: usize,
+    concurrency: usize,
+    offset_seconds: f64,
+    requests: usize,
+    errors: usize,
+    rps: f64,
+    active_connections: usize,
+    mean_latency_ms: f64,
+    p50_latency_ms: u64,
+    p95_latency_ms: u64,
+    p99_latency_ms: u64,
+}
+
+/// Write flat CSV tables (scenarios, endpoints, HTTP and gRPC status codes, time series) into `dir`.
+/// Scenario numbers are 1-based and join the tables together.
+pub fn save_csv_tables(report: &LoadTestReport, dir: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    fs::create_dir_all(dir)?;
+
+    let mut scenarios = csv::Writer::from_path(dir.join("scenarios.csv"))?;
+    let mut endpoints = csv::Writer::from_path(dir.join("endpoints.csv"))?;
+    let mut status_codes = csv::Writer::from_path(dir.join("status_codes.csv"))?;
+    let mut grpc_status_codes = csv::Writer::from_path(dir.join("grpc_status_codes.csv"))?;
+    let mut time_series = csv::Writer::from_path(dir.join("time_series.csv"))?;
+
+    for (index, scenario) in report.scenarios.iter().enumerate() {
+        let number = index + 1;
+        scenarios.serialize(ScenarioRow {
+            scenario: number,
+            concurrency: scenario.concurrency,
+            total_requests: scenario.total_requests,
+            successful_requests: scenario.successful_requests,
+            failed_requests: scenario.failed_requests,
+            success_rate: scenario.success_rate,
+            rps: scenario.rps,
+            mean_latency_ms: scenario.mean_latency,
+            p95_latency_ms: scenario.p95_latency,
+            p99_latency_ms: scenario.p99_latency,
+            duration_seconds: scenario.duration_seconds,
+        })?;
+
+        for endpoint in &scenario.endpoints {
+            endpoints.serialize(EndpointRow {
+                scenario: number,
+                concurrency: scenario.concurrency,
+                endpoint: &endpoint.endpoint,
+                total_requests: endpoint.total_requests,
+                successful_requests: endpoint.successful_requests,
+                failed_requests: endpoint.failed_requests,
+                success_rate: endpoint.success_rate,
+                rps: endpoint.rps,
+                mean_latency_ms: endpoint.mean_latency,
+                p95_latency_ms: endpoint.p95_latency,
+                p99_latency_ms: endpoint.p99_latency,
+                duration_seconds: endpoint.duration_seconds,
+            })?;
+
+            let mut codes: Vec<_> = endpoint.status_codes.iter().collect();
+            codes.sort();
+            for (&status, &count) in codes {
+                status_codes.serialize(StatusCodeRow {
+                    scenario: number,
+                    concurrency: scenario.concurrency,
+                    endpoint: &endpoint.endpoint,
+                    status,
+                    count,
+                })?;
+            }
+
+            let mut codes: Vec<_> = endpoint.grpc_status_codes.iter().collect();
+            codes.sort();
+            for (&grpc_code, &count) in codes {
+                grpc_status_codes.serialize(GrpcStatusCodeRow {
+                    scenario: number,
+                    concurrency: scenario.concurrency,
+                    endpoint: &endpoint.endpoint,
+                    grpc_code,
+                    count,
+                })?;
+            }
+        }
+
+        for bucket in &scenario.time_series {
+            time_series.serialize(TimeSeriesRow {
+                scenario: number,
+                concurrency: scenario.concurrency,
+                offset_seconds: bucket.offset_seconds,
+                requests: bucket.requests,
+                errors: bucket.errors,
+                rps: bucket.rps,
+                active_connections: bucket.active_connections,
+                mean_latency_ms: bucket.mean_latency,
+                p50_latency_ms: bucket.p50_latency,
+                p95_latency_ms: bucket.p95_latency,
+                p99_latency_ms: bucket.p99_latency,
+            })?;
+        }
+    }
+
+    for writer in [&mut scenarios, &mut endpoints, &mut status_codes, &mut grpc_status_codes, &mut time_series] {
+        writer.flush()?;
+    }
+
+    Ok(["scenarios.csv", "endpoints.csv", "status_codes.csv", "grpc_status_codes.csv", "time_series.csv"]
+        .iter()
+        .map(|name| dir.join(name))
+        .collect())
+}
+
+/// Default `--record-requests` file: Parquet when built with the `parquet` feature, CSV otherwise
+pub const DEFAULT_REQUEST_RECORDS: &str = if cfg!(feature = "parquet") { "requests.parquet" } else { "requests.csv" };
+
+/// Rows buffered before they are written to the Parquet file as one record batch
+#[cfg(feature = "parquet")]
+const PARQUET_BATCH_ROWS: usize = 8192;
+
+#[derive(Serialize)]
+struct RequestRow<'a> {
+    timestamp: String,
+    scenario: usize,
+    concurrency: usize,
+    endpoint: &'a str,
+    status: Option<u16>,
+    latency_ms: u64,
+    bytes: u64,
+    error_class: Option<&'a str>,
+    grpc_code: Option<u16>,
+}
+
+enum RecordFormat {
+    Csv(csv::Writer<fs::File>),
+    #[cfg(feature = "parquet")]
+    Parquet {
+        writer: Option<parquet::arrow::ArrowWriter<fs::File>>, // Taken when the file is closed
+        pending: Vec<RequestRecord>,
+    },
+}
+
+/// Streams one row per completed request to a file as requests finish, so raw data never accumulates in memory:
+/// timestamp (UTC, ms), scenario, concurrency, endpoint, status, latency_ms, bytes, error_class, grpc_code.
+/// Paths ending in `.parquet` are written as Parquet (parquet feature), anything else as CSV.
+pub struct RequestRecordWriter {
+    path: PathBuf,
+    format: RecordFormat,
+    rows: u64,
+}
+
+impl RequestRecordWriter {
+    pub fn create(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let is_parquet = path.extension().is_some_and(|extension| extension == "parquet");
+        let format = if is_parquet {
+            #[cfg(feature = "parquet")]
+            {
+                let writer = parquet::arrow::ArrowWriter::try_new(fs::File::create(path)?, request_schema(), None)?;
+                RecordFormat::Parquet { writer: Some(writer), pending: Vec::with_capacity(PARQUET_BATCH_ROWS) }
+            }
+            #[cfg(not(feature = "parquet"))]
+            return Err("writing Parquet needs a build with `--features parquet`".into());
+        } else {
+            RecordFormat::Csv(csv::Writer::from_path(path)?)
+        };
+        Ok(Self { path: path.to_path_buf(), format, rows: 0 })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn write(&mut self, record: RequestRecord) -> Result<(), Box<dyn std::error::Error>> {
+        self.rows += 1;
+        match &mut self.format {
+            RecordFormat::Csv(writer) => writer.serialize(RequestRow {
+                timestamp: record.timestamp.with_timezone(&Utc).to_rfc3339_opts(SecondsFormat::Millis, true),
+                scenario: record.scenario,
+                concurrency: record.concurrency,
+                endpoint: &record.endpoint,
+                status: record.status,
+                latency_ms: record.latency_ms,
+                bytes: record.bytes,
+                error_class: record.error_class.map(|class| class.as_str()),
+                grpc_code: record.grpc_code,
+            })?,
+            #[cfg(feature = "parquet")]
+            RecordFormat::Parquet { writer, pending } => {
+                pending.push(record);
+                if pending.len() >= PARQUET_BATCH_ROWS
+                    && let Some(writer) = writer
+                {
+                    writer.write(&request_batch(pending)?)?;
+                    pending.clear();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write buffered rows and close the file; returns the rows written
+    pub fn finish(&mut self) -> Result<u64, Box<dyn std::error::Error>> {
+        match &mut self.format {
+            RecordFormat::Csv(writer) => writer.flush()?,
+            #[cfg(feature = "parquet")]
+            RecordFormat::Parquet { writer, pending } => {
+                if let Some(mut writer) = writer.take() {
+                    if !pending.is_empty() {
+                        writer.write(&request_batch(pending)?)?;
+                        pending.clear();
+                    }
+                    writer.close()?;
+                }
+            }
+        }
+        Ok(self.rows)
+    }
+}
+
+#[cfg(feature = "parquet")]
+fn request_schema() -> std::sync::Arc<arrow_schema::Schema> {
+    use arrow_schema::{DataType, Field, Schema, TimeUnit};
+
+    std::sync::Arc::new(Schema::new(vec![
+        Field::new("timestamp", DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())), false),
+        Field::new("scenario", DataType::UInt64, false),
+        Field::new("concurrency", DataType::UInt64, false),
+        Field::new("endpoint", DataType::Utf8, false),
+        Field::new("status", DataType::UInt16, true),
+        Field::new("latency_ms", DataType::UInt64, false),
+        Field::new("bytes", DataType::UInt64, false),
+        Field::new("error_class", DataType::Utf8, true),
+        Field::new("grpc_code", DataType::UInt16, true),
+    ]))
+}
+
+#[cfg(feature = "parquet")]
+fn request_batch(requests: &[RequestRecord]) -> Result<arrow_array::RecordBatch, arrow_schema::ArrowError> {
+    use arrow_array::{ArrayRef, RecordBatch, StringArray, TimestampMillisecondArray, UInt16Array, UInt64Array};
+    use std::sync::Arc;
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(
+            TimestampMillisecondArray::from_iter_values(requests.iter().map(|r| r.timestamp.timestamp_millis()))
+                .with_timezone("UTC"),
+        ),
+        Arc::new(UInt64Array::from_iter_values(requests.iter().map(|r| r.scenario as u64))),
+        Arc::new(UInt64Array::from_iter_values(requests.iter().map(|r| r.concurrency as u64))),
+        Arc::new(StringArray::from_iter_values(requests.iter().map(|r| r.endpoint.as_str()))),
+        Arc::new(UInt16Array::from_iter(requests.iter().map(|r| r.status))),
+        Arc::new(UInt64Array::from_iter_values(requests.iter().map(|r| r.latency_ms))),
+        Arc::new(UInt64Array::from_iter_values(requests.iter().map(|r| r.bytes))),
+        Arc::new(StringArray::from_iter(requests.iter().map(|r| r.error_class.map(|class| class.as_str())))),
+        Arc::new(UInt16Array::from_iter(requests.iter().map(|r| r.grpc_code))),
+    ];
+    RecordBatch::try_new(request_schema(), columns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ErrorClass;
+    use crate::models::fixtures::{endpoint, report, scenario};
+    use chrono::{DateTime, Local};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("load-test-rs-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn record(index: usize) -> RequestRecord {
+        RequestRecord {
+            timestamp: DateTime::parse_from_rfc3339("2024-05-01T12:00:00.250+00:00").unwrap().with_timezone(&Local),
+            scenario: 1,
+            concurrency: 10,
+            endpoint: "/users".to_string(),
+            status: index.is_multiple_of(2).then_some(200),
+            latency_ms: index as u64,
+            bytes: 512,
+            error_class: (!index.is_multiple_of(2)).then_some(ErrorClass::Timeout),
+            grpc_code: None,
+        }
+    }
+
+    #[test]
+    fn csv_tables_have_one_row_per_scenario_and_endpoint() {
+        let dir = temp_dir("csv-tables");
+        let report = report(vec![
+            scenario(10, vec![endpoint("/users", 100, 5, 80), endpoint("/orders", 50, 0, 120)]),
+            scenario(20, vec![endpoint("/users", 200, 10, 90)]),
+        ]);
+
+        let files = save_csv_tables(&report, &dir).unwrap();
+        assert_eq!(files.len(), 5);
+        let scenarios = fs::read_to_string(dir.join("scenarios.csv")).unwrap();
+        let endpoints = fs::read_to_string(dir.join("endpoints.csv")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(scenarios.lines().count(), 3);
+        assert_eq!(endpoints.lines().count(), 4);
+        assert!(endpoints.lines().any(|line| line.contains("/orders")));
+    }
+
+    #[test]
+    fn request_records_stream_to_csv() {
+        let dir = temp_dir("request-records");
+        let path = dir.join("requests.csv");
+        let mut writer = RequestRecordWriter::create(&path).unwrap();
+        for index in 0..3 {
+            writer.write(record(index)).unwrap();
+        }
+        assert_eq!(writer.finish().unwrap(), 3);
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(
+            lines[0],
+            "timestamp,scenario,concurrency,endpoint,status,latency_ms,bytes,error_class,grpc_code"
+        );
+        assert_eq!(lines[1], "2024-05-01T12:00:00.250Z,1,10,/users,200,0,512,,");
+        assert_eq!(lines[2], "2024-05-01T12:00:00.250Z,1,10,/users,,1,512,timeout,");
+        assert_eq!(lines.len(), 4);
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn request_records_stream_to_parquet_in_batches() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let dir = temp_dir("request-records-parquet");
+        let path = dir.join("requests.parquet");
+        let mut writer = RequestRecordWriter::create(&path).unwrap();
+        let rows = PARQUET_BATCH_ROWS + 10;
+        for index in 0..rows {
+            writer.write(record(index)).unwrap();
+        }
+        assert_eq!(writer.finish().unwrap(), rows as u64);
+        let reader = SerializedFileReader::new(fs::File::open(&path).unwrap()).unwrap();
+        let written = reader.metadata().file_metadata().num_rows();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(written, rows as i64);
+    }
+}
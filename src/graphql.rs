@@ -0,0 +1,128 @@
+use crate::config::LoadTestConfig;
+use crate::templates::JsonTemplate;
+use serde_json::json;
+
+/// Default path GraphQL operations are posted to, appended to the base URL
+pub const DEFAULT_GRAPHQL_PATH: &str = "/graphql";
+
+/// A GraphQL operation sent in every scenario after the GET endpoints.
+///
+/// Results are reported per operation name, since every operation shares one URL. A response is a
+/// failure when its status isn't 2xx, its body isn't JSON, or its `errors` array is non-empty.
+#[derive(Clone, Debug)]
+pub struct GraphqlOperation {
+    pub path: String,              // GraphQL endpoint, appended to the base URL
+    pub operation_name: String,    // Sent as `operationName`, selecting the operation when the document has several
+    pub query: String,             // Query document
+    pub variables: Option<String>, // minijinja template rendering a JSON object; see `JsonTemplate`
+}
+
+impl GraphqlOperation {
+    pub fn new(operation_name: &str, query: &str) -> Self {
+        Self {
+            path: DEFAULT_GRAPHQL_PATH.to_string(),
+            operation_name: operation_name.to_string(),
+            query: query.to_string(),
+            variables: None,
+        }
+    }
+
+    /// Name of the operation's row among the endpoint results, e.g. "GraphQL GetUserGoals"
+    pub fn endpoint_name(&self) -> String {
+        format!("GraphQL {}", self.operation_name)
+    }
+}
+
+/// A `GraphqlOperation` with its variables template compiled, shared by the requests of a run
+pub(crate) struct GraphqlRequest {
+    pub(crate) operation: GraphqlOperation,
+    variables: Option<JsonTemplate>,
+}
+
+impl GraphqlRequest {
+    pub(crate) fn new(operation: &GraphqlOperation, config: &LoadTestConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        let variables = operation.variables.as_deref()
+            .map(|variables| JsonTemplate::new(variables, config))
+            .transpose()
+            .map_err(|e| format!("variables: {}", e))?;
+        Ok(Self { operation: operation.clone(), variables })
+    }
+
+    /// JSON body for the `request_index`-th request, sent by virtual user `user`
+    pub(crate) fn body(&self, request_index: usize, user: usize) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        let variables = match &self.variables {
+            Some(variables) => variables.render(request_index, user).map_err(|e| format!("variables: {}", e))?,
+            None => json!({}),
+        };
+        Ok(json!({
+            "query": self.operation.query,
+            "operationName": self.operation.operation_name,
+            "variables": variables,
+        }))
+    }
+}
+
+/// Why a 2xx GraphQL response body counts as a failure, `None` when it doesn't
+pub(crate) fn response_error(body: &[u8]) -> Option<String> {
+    let response: serde_json::Value = match serde_json::from_slice(body) {
+        Ok(response) => response,
+        Err(e) => return Some(format!("response is not JSON: {}", e)),
+    };
+    match response.get("errors").and_then(|errors| errors.as_array()) {
+        Some(errors) if !errors.is_empty() => {
+            let message = errors[0].get("message").and_then(|message| message.as_str()).unwrap_or("no message");
+            Some(format!("{} GraphQL error(s), first: {}", errors.len(), message))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn response_error_accepts_data_and_empty_errors() {
+        assert_eq!(response_error(br#"{"data":{"user":{"id":"1"}}}"#), None);
+        assert_eq!(response_error(br#"{"data":null,"errors":[]}"#), None);
+        assert_eq!(response_error(br#"{"data":{},"errors":null}"#), None);
+    }
+
+    #[test]
+    fn response_error_reports_the_first_error() {
+        let body = br#"{"data":null,"errors":[{"message":"Not authorized"},{"message":"Other"}]}"#;
+        assert_eq!(response_error(body).unwrap(), "2 GraphQL error(s), first: Not authorized");
+        assert_eq!(response_error(br#"{"errors":[{"path":["user"]}]}"#).unwrap(), "1 GraphQL error(s), first: no message");
+    }
+
+    #[test]
+    fn response_error_rejects_bodies_that_are_not_json() {
+        assert!(response_error(b"<html>Bad gateway</html>").unwrap().starts_with("response is not JSON: "));
+        assert!(response_error(b"").is_some());
+    }
+
+    #[test]
+    fn body_renders_variables_per_request() {
+        let config = LoadTestConfig::new();
+        let mut operation = GraphqlOperation::new("GetGoal", "query GetGoal($n: Int!) { goal(n: $n) { id } }");
+        operation.variables = Some(r#"{"n": {{ request }}, "user": {{ user }}, "owner": "{{ user_id }}"}"#.to_string());
+        let request = GraphqlRequest::new(&operation, &config).unwrap();
+
+        let body = request.body(7, 3).unwrap();
+        assert_eq!(body["operationName"], "GetGoal");
+        assert_eq!(body["query"], operation.query);
+        assert_eq!(body["variables"], json!({ "n": 7, "user": 3, "owner": config.user_id }));
+        assert_eq!(operation.endpoint_name(), "GraphQL GetGoal");
+
+        let plain = GraphqlRequest::new(&GraphqlOperation::new("Ping", "{ ping }"), &config).unwrap();
+        assert_eq!(plain.body(0, 0).unwrap()["variables"], json!({}));
+    }
+
+    #[test]
+    fn variables_that_are_not_json_are_rejected_up_front() {
+        let mut operation = GraphqlOperation::new("GetGoal", "{ goal }");
+        operation.variables = Some("{ n: {{ request }} }".to_string());
+        let error = GraphqlRequest::new(&operation, &LoadTestConfig::new()).err().unwrap();
+        assert!(error.to_string().starts_with("variables: not JSON"));
+    }
+}
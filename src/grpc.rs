@@ -0,0 +1,498 @@
+use crate::config::LoadTestConfig;
+use crate::http_client::{HttpClientConfig, HttpVersion};
+use crate::models::ErrorClass;
+use crate::templates::JsonTemplate;
+use hyper::body::HttpBody;
+use hyper::client::HttpConnector;
+use hyper::header::HeaderMap;
+use hyper::{Body, Client, Request, StatusCode, Uri};
+use hyper_rustls::HttpsConnector;
+use prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage, MethodDescriptor};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+const GRPC_OK: u16 = 0;
+const GRPC_UNKNOWN: u16 = 2;
+const GRPC_DEADLINE_EXCEEDED: u16 = 4;
+const GRPC_UNIMPLEMENTED: u16 = 12;
+const GRPC_INTERNAL: u16 = 13;
+const GRPC_UNAVAILABLE: u16 = 14;
+
+/// Where a gRPC call's service definitions come from
+#[derive(Clone, Debug)]
+pub enum GrpcDescriptors {
+    ProtoFiles {
+        files: Vec<PathBuf>,
+        include_paths: Vec<PathBuf>, // Imports resolve against these; defaults to each file's directory
+    },
+    Reflection, // Fetched from the server's reflection service, v1 then v1alpha
+}
+
+/// A gRPC method called in every scenario after the HTTP endpoints, one result row each.
+///
+/// The row's status codes stay HTTP statuses (200 for a gRPC response) and its gRPC codes
+/// (0 = OK) are counted separately. Unary and server-streaming methods are supported; a
+/// streaming call is timed until its stream ends.
+#[derive(Clone, Debug)]
+pub struct GrpcCall {
+    pub target: String, // e.g. `http://127.0.0.1:50051`; https targets use the client's TLS settings
+    pub method: String, // `package.Service/Method`
+    pub descriptors: GrpcDescriptors,
+    pub message: String, // minijinja template rendering the request message as protobuf JSON
+}
+
+impl GrpcCall {
+    pub fn new(target: &str, method: &str, descriptors: GrpcDescriptors) -> Self {
+        Self {
+            target: target.trim_end_matches('/').to_string(),
+            method: method.to_string(),
+            descriptors,
+            message: "{}".to_string(),
+        }
+    }
+
+    /// Name of the call's row among the endpoint results, e.g. "gRPC goals.Goals/GetGoal"
+    pub fn endpoint_name(&self) -> String {
+        format!("gRPC {}", self.method)
+    }
+
+    /// `/package.Service/Method`, the request path; also the key for `endpoint_http_clients` overrides
+    pub fn path(&self) -> String {
+        format!("/{}", self.method.trim_start_matches('/'))
+    }
+
+    /// Request URL: the target plus `path`
+    pub fn url(&self) -> String {
+        format!("{}{}", self.target, self.path())
+    }
+}
+
+/// How one call ended
+pub(crate) struct GrpcOutcome {
+    pub(crate) http_status: Option<u16>, // None when no response was received
+    pub(crate) code: u16, // From the server, or set client-side as gRPC clients do, e.g. UNAVAILABLE when it can't connect
+    pub(crate) error_class: Option<ErrorClass>,
+    pub(crate) error: Option<String>,
+    pub(crate) bytes: u64, // Response messages, framing included
+}
+
+impl GrpcOutcome {
+    fn failed(code: u16, error_class: ErrorClass, error: impl ToString) -> Self {
+        Self { http_status: None, code, error_class: Some(error_class), error: Some(error.to_string()), bytes: 0 }
+    }
+}
+
+/// A `GrpcCall` with its method resolved and message template compiled, shared by the requests of a run.
+///
+/// Calls share one HTTP/2 client and connect directly to the target; `resolve`, `proxy` and
+/// `source_addresses` are not applied.
+pub(crate) struct GrpcClient {
+    pub(crate) call: GrpcCall,
+    method: MethodDescriptor,
+    message: JsonTemplate,
+    client: Client<HttpsConnector<HttpConnector>>,
+    runtime: Runtime,
+    timeout: Duration,
+}
+
+impl GrpcClient {
+    pub(crate) fn new(
+        call: &GrpcCall,
+        config: &LoadTestConfig,
+        client_config: &HttpClientConfig,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let (service_name, method_name) = call.method.trim_start_matches('/')
+            .rsplit_once('/')
+            .ok_or_else(|| format!("method {} is not package.Service/Method", call.method))?;
+
+        let mut http = HttpConnector::new();
+        http.enforce_http(false);
+        http.set_connect_timeout(Some(client_config.connect_timeout));
+        http.set_nodelay(true);
+        let mut tls = client_config.tls.client_config(HttpVersion::Http2PriorKnowledge)?;
+        // The connector sets ALPN itself from the enabled protocols
+        tls.alpn_protocols.clear();
+        let connector = hyper_rustls::HttpsConnectorBuilder::new()
+            .with_tls_config(tls)
+            .https_or_http()
+            .enable_http2()
+            .wrap_connector(http);
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .enable_all()
+            .build()?;
+        let client = Client::builder().http2_only(true).build(connector);
+        let timeout = client_config.request_timeout;
+
+        let pool = match &call.descriptors {
+            GrpcDescriptors::ProtoFiles { files, include_paths } => parse_proto_files(files, include_paths)?,
+            GrpcDescriptors::Reflection => runtime.block_on(reflect(&client, &call.target, service_name, timeout))?,
+        };
+        let service = pool.get_service_by_name(service_name)
+            .ok_or_else(|| format!("service {} not found", service_name))?;
+        let method = service.methods()
+            .find(|method| method.name() == method_name)
+            .ok_or_else(|| format!("method {} not found in {}", method_name, service_name))?;
+        if method.is_client_streaming() {
+            return Err(format!("{} is client-streaming; only unary and server-streaming calls are supported", call.method).into());
+        }
+
+        let client = Self {
+            call: call.clone(),
+            method,
+            message: JsonTemplate::new(&call.message, config)?,
+            client,
+            runtime,
+            timeout,
+        };
+        // A template that doesn't fit the request type would fail every call; catch it before the run
+        client.encode(0, 0)?;
+        Ok(client)
+    }
+
+    /// Send the `request_index`-th call, from virtual user `user`, with `metadata` as extra headers
+    pub(crate) fn call(&self, request_index: usize, user: usize, metadata: &[(&str, String)]) -> GrpcOutcome {
+        let body = match self.encode(request_index, user) {
+            Ok(body) => body,
+            Err(e) => return GrpcOutcome::failed(GRPC_INTERNAL, ErrorClass::Other, e),
+        };
+        let uri = match self.call.url().parse::<Uri>() {
+            Ok(uri) => uri,
+            Err(e) => return GrpcOutcome::failed(GRPC_INTERNAL, ErrorClass::Other, e),
+        };
+
+        let response = self.runtime.block_on(async {
+            tokio::time::timeout(self.timeout, exchange(&self.client, uri, body, metadata, self.timeout)).await
+        });
+        let (http_status, code, message, frames) = match response {
+            Ok(Ok(response)) => response,
+            Ok(Err(e)) if e.is_connect() => return GrpcOutcome::failed(GRPC_UNAVAILABLE, ErrorClass::Connect, e),
+            Ok(Err(e)) => return GrpcOutcome::failed(GRPC_UNAVAILABLE, ErrorClass::Other, e),
+            Err(_) => return GrpcOutcome::failed(GRPC_DEADLINE_EXCEEDED, ErrorClass::Timeout, "deadline exceeded"),
+        };
+
+        let bytes = frames.len() as u64;
+        let error = if code != GRPC_OK {
+            Some(format!("{}: {}", code_name(code), message))
+        } else {
+            split_frames(&frames)
+                .and_then(|messages| {
+                    messages.into_iter()
+                        .try_for_each(|message| DynamicMessage::decode(self.method.output(), message).map(|_| ()))
+                        .map_err(|e| e.to_string())
+                })
+                .err()
+                .map(|e| format!("invalid response message: {}", e))
+        };
+        let error_class = match code {
+            GRPC_OK if error.is_some() => Some(ErrorClass::Other),
+            code => error_class(code),
+        };
+        GrpcOutcome { http_status: Some(http_status), code, error_class, error, bytes }
+    }
+
+    /// Framed request message for the `request_index`-th call
+    fn encode(&self, request_index: usize, user: usize) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let json = self.message.render(request_index, user)?;
+        let message = DynamicMessage::deserialize(self.method.input(), json)
+            .map_err(|e| format!("message doesn't fit {}: {}", self.method.input().full_name(), e))?;
+        Ok(frame(&message.encode_to_vec()))
+    }
+}
+
+/// POST one framed message and collect the response: (HTTP status, gRPC status, status message, response frames)
+async fn exchange(
+    client: &Client<HttpsConnector<HttpConnector>>,
+    uri: Uri,
+    body: Vec<u8>,
+    metadata: &[(&str, String)],
+    timeout: Duration,
+) -> Result<(u16, u16, String, Vec<u8>), hyper::Error> {
+    let mut request = Request::post(uri)
+        .header("content-type", "application/grpc")
+        .header("te", "trailers")
+        .header("grpc-timeout", format!("{}m", timeout.as_millis()));
+    for (name, value) in metadata {
+        request = request.header(name.to_ascii_lowercase(), value.as_str());
+    }
+    let request = request.body(Body::from(body)).expect("gRPC request headers are valid");
+
+    let response = client.request(request).await?;
+    let http_status = response.status();
+    // Trailers-only responses, usually errors, carry the status in the headers
+    let header_status = grpc_status(response.headers());
+    let mut body = response.into_body();
+    let mut frames = Vec::new();
+    while let Some(chunk) = body.data().await {
+        frames.extend_from_slice(&chunk?);
+    }
+    let trailer_status = body.trailers().await?.as_ref().and_then(grpc_status);
+
+    let (code, message) = header_status.or(trailer_status).unwrap_or_else(|| {
+        if http_status == StatusCode::OK {
+            (GRPC_UNKNOWN, "response without grpc-status".to_string())
+        } else {
+            (code_from_http(http_status), format!("HTTP {}", http_status))
+        }
+    });
+    Ok((http_status.as_u16(), code, message, frames))
+}
+
+fn grpc_status(headers: &HeaderMap) -> Option<(u16, String)> {
+    let code = headers.get("grpc-status")?.to_str().ok()?.parse().ok()?;
+    let message = headers.get("grpc-message")
+        .and_then(|message| message.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    Some((code, message))
+}
+
+/// Length-prefixed, uncompressed gRPC message
+fn frame(message: &[u8]) -> Vec<u8> {
+    let mut framed = Vec::with_capacity(message.len() + 5);
+    framed.push(0);
+    framed.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    framed.extend_from_slice(message);
+    framed
+}
+
+fn split_frames(mut data: &[u8]) -> Result<Vec<&[u8]>, String> {
+    let mut messages = Vec::new();
+    while !data.is_empty() {
+        if data.len() < 5 {
+            return Err("truncated message header".to_string());
+        }
+        if data[0] != 0 {
+            return Err("compressed messages are not supported".to_string());
+        }
+        let length = u32::from_be_bytes([data[1], data[2], data[3], data[4]]) as usize;
+        let message = data.get(5..5 + length).ok_or("truncated message")?;
+        messages.push(message);
+        data = &data[5 + length..];
+    }
+    Ok(messages)
+}
+
+/// Error class matching a gRPC status, following the usual gRPC to HTTP mapping
+fn error_class(code: u16) -> Option<ErrorClass> {
+    match code {
+        GRPC_OK => None,
+        GRPC_DEADLINE_EXCEEDED => Some(ErrorClass::Timeout),
+        3 | 5 | 6 | 7 | 9 | 11 | 16 => Some(ErrorClass::ClientError), // Caller mistakes, e.g. INVALID_ARGUMENT, NOT_FOUND
+        2 | 8 | 10 | 12 | 13 | 14 | 15 => Some(ErrorClass::ServerError),
+        _ => Some(ErrorClass::Other), // CANCELLED and unknown codes
+    }
+}
+
+/// gRPC status for a response that isn't gRPC, as gRPC clients derive it
+fn code_from_http(status: StatusCode) -> u16 {
+    match status.as_u16() {
+        400 => GRPC_INTERNAL,
+        401 => 16,
+        403 => 7,
+        404 => GRPC_UNIMPLEMENTED,
+        429 | 502 | 503 | 504 => GRPC_UNAVAILABLE,
+        _ => GRPC_UNKNOWN,
+    }
+}
+
+fn code_name(code: u16) -> &'static str {
+    match code {
+        0 => "OK",
+        1 => "CANCELLED",
+        2 => "UNKNOWN",
+        3 => "INVALID_ARGUMENT",
+        4 => "DEADLINE_EXCEEDED",
+        5 => "NOT_FOUND",
+        6 => "ALREADY_EXISTS",
+        7 => "PERMISSION_DENIED",
+        8 => "RESOURCE_EXHAUSTED",
+        9 => "FAILED_PRECONDITION",
+        10 => "ABORTED",
+        11 => "OUT_OF_RANGE",
+        12 => "UNIMPLEMENTED",
+        13 => "INTERNAL",
+        14 => "UNAVAILABLE",
+        15 => "DATA_LOSS",
+        16 => "UNAUTHENTICATED",
+        _ => "UNRECOGNIZED",
+    }
+}
+
+/// Parse `.proto` files in-process, with the files they import
+fn parse_proto_files(files: &[PathBuf], include_paths: &[PathBuf]) -> Result<DescriptorPool, Box<dyn std::error::Error>> {
+    let includes: Vec<PathBuf> = if include_paths.is_empty() {
+        // A bare file name's parent is the empty path
+        files.iter()
+            .map(|file| file.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new(".")).to_path_buf())
+            .collect()
+    } else {
+        include_paths.to_vec()
+    };
+    let mut compiler = protox::Compiler::new(includes)?;
+    compiler.include_imports(true).open_files(files)?;
+    Ok(compiler.descriptor_pool())
+}
+
+/// Fetch the files defining `service`, and their imports, from the target's reflection service
+async fn reflect(
+    client: &Client<HttpsConnector<HttpConnector>>,
+    target: &str,
+    service: &str,
+    timeout: Duration,
+) -> Result<DescriptorPool, Box<dyn std::error::Error>> {
+    let request = ServerReflectionRequest { host: String::new(), file_containing_symbol: Some(service.to_string()) };
+    let mut last_error = String::new();
+    for version in ["v1", "v1alpha"] {
+        let uri: Uri = format!("{}/grpc.reflection.{}.ServerReflection/ServerReflectionInfo", target, version).parse()?;
+        let exchanged = tokio::time::timeout(timeout, exchange(client, uri, frame(&request.encode_to_vec()), &[], timeout))
+            .await
+            .map_err(|_| "reflection request timed out")??;
+        let (_, code, message, frames) = exchanged;
+        if code != GRPC_OK {
+            last_error = format!("reflection {}: {}: {}", version, code_name(code), message);
+            if code == GRPC_UNIMPLEMENTED {
+                continue;
+            }
+            break;
+        }
+
+        let mut files = Vec::new();
+        for message in split_frames(&frames)? {
+            let response = ServerReflectionResponse::decode(message)?;
+            if let Some(error) = response.error_response {
+                return Err(format!("reflection: {} (code {})", error.error_message, error.error_code).into());
+            }
+            for file in response.file_descriptor_response.map(|files| files.file_descriptor_proto).unwrap_or_default() {
+                files.push(prost_reflect::prost_types::FileDescriptorProto::decode(file.as_slice())?);
+            }
+        }
+        let mut pool = DescriptorPool::new();
+        pool.add_file_descriptor_protos(files)?;
+        return Ok(pool);
+    }
+    Err(last_error.into())
+}
+
+// The parts of grpc.reflection.v1 (and the identical v1alpha) used to look up a service;
+// the `oneof` fields are declared as optionals, which encode the same way
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct ServerReflectionRequest {
+    #[prost(string, tag = "1")]
+    host: String,
+    #[prost(string, optional, tag = "4")]
+    file_containing_symbol: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct ServerReflectionResponse {
+    #[prost(message, optional, tag = "4")]
+    file_descriptor_response: Option<FileDescriptorResponse>,
+    #[prost(message, optional, tag = "7")]
+    error_response: Option<ErrorResponse>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct FileDescriptorResponse {
+    #[prost(bytes = "vec", repeated, tag = "1")]
+    file_descriptor_proto: Vec<Vec<u8>>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct ErrorResponse {
+    #[prost(int32, tag = "1")]
+    error_code: i32,
+    #[prost(string, tag = "2")]
+    error_message: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::header::HeaderValue;
+
+    #[test]
+    fn call_names_path_and_url() {
+        let call = GrpcCall::new("http://127.0.0.1:50051/", "goals.Goals/GetGoal", GrpcDescriptors::Reflection);
+        assert_eq!(call.endpoint_name(), "gRPC goals.Goals/GetGoal");
+        assert_eq!(call.path(), "/goals.Goals/GetGoal");
+        assert_eq!(call.url(), "http://127.0.0.1:50051/goals.Goals/GetGoal");
+    }
+
+    #[test]
+    fn frames_round_trip() {
+        let mut data = frame(b"first");
+        data.extend(frame(b""));
+        data.extend(frame(&[7; 300]));
+        assert_eq!(&data[..5], &[0, 0, 0, 0, 5]);
+        let messages = split_frames(&data).unwrap();
+        assert_eq!(messages, vec![&b"first"[..], &b""[..], &[7; 300][..]]);
+        assert!(split_frames(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn split_frames_rejects_bad_framing() {
+        assert_eq!(split_frames(&[0, 0, 0]).unwrap_err(), "truncated message header");
+        assert_eq!(split_frames(&[0, 0, 0, 0, 4, 1]).unwrap_err(), "truncated message");
+        assert_eq!(split_frames(&[1, 0, 0, 0, 0]).unwrap_err(), "compressed messages are not supported");
+    }
+
+    #[test]
+    fn status_is_read_from_headers_or_trailers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(grpc_status(&headers), None);
+        headers.insert("grpc-status", HeaderValue::from_static("5"));
+        assert_eq!(grpc_status(&headers), Some((5, String::new())));
+        headers.insert("grpc-message", HeaderValue::from_static("goal not found"));
+        assert_eq!(grpc_status(&headers), Some((5, "goal not found".to_string())));
+        headers.insert("grpc-status", HeaderValue::from_static("not a number"));
+        assert_eq!(grpc_status(&headers), None);
+    }
+
+    #[test]
+    fn codes_map_to_error_classes() {
+        assert_eq!(error_class(GRPC_OK), None);
+        assert_eq!(error_class(GRPC_DEADLINE_EXCEEDED), Some(ErrorClass::Timeout));
+        assert_eq!(error_class(5), Some(ErrorClass::ClientError));
+        assert_eq!(error_class(16), Some(ErrorClass::ClientError));
+        assert_eq!(error_class(GRPC_UNAVAILABLE), Some(ErrorClass::ServerError));
+        assert_eq!(error_class(1), Some(ErrorClass::Other));
+        assert_eq!(error_class(99), Some(ErrorClass::Other));
+    }
+
+    #[test]
+    fn http_statuses_map_to_codes() {
+        assert_eq!(code_from_http(StatusCode::BAD_REQUEST), GRPC_INTERNAL);
+        assert_eq!(code_name(code_from_http(StatusCode::UNAUTHORIZED)), "UNAUTHENTICATED");
+        assert_eq!(code_name(code_from_http(StatusCode::FORBIDDEN)), "PERMISSION_DENIED");
+        assert_eq!(code_from_http(StatusCode::NOT_FOUND), GRPC_UNIMPLEMENTED);
+        assert_eq!(code_from_http(StatusCode::SERVICE_UNAVAILABLE), GRPC_UNAVAILABLE);
+        assert_eq!(code_from_http(StatusCode::INTERNAL_SERVER_ERROR), GRPC_UNKNOWN);
+        assert_eq!(code_name(42), "UNRECOGNIZED");
+    }
+
+    #[test]
+    fn proto_files_resolve_imports() {
+        let dir = std::env::temp_dir().join(format!("load-test-rs-proto-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("common.proto"), "syntax = \"proto3\";\npackage common;\nmessage Id { string id = 1; }\n").unwrap();
+        std::fs::write(
+            dir.join("goals.proto"),
+            "syntax = \"proto3\";\npackage goals;\nimport \"common.proto\";\nimport \"google/protobuf/timestamp.proto\";\n\
+             message Goal { string name = 1; google.protobuf.Timestamp due = 2; }\n\
+             service Goals { rpc GetGoal(common.Id) returns (Goal); }\n",
+        )
+        .unwrap();
+
+        let pool = parse_proto_files(&[dir.join("goals.proto")], &[]).unwrap();
+        let method = pool.get_service_by_name("goals.Goals").unwrap().methods().next().unwrap();
+        assert_eq!(method.input().full_name(), "common.Id");
+        assert_eq!(method.output().full_name(), "goals.Goal");
+        assert!(pool.get_message_by_name("google.protobuf.Timestamp").is_some());
+        assert!(parse_proto_files(&[dir.join("missing.proto")], &[]).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
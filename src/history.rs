@@ -0,0 +1,420 @@
+use crate::models::LoadTestReport;
+use crate::svg_charts::{self, Series, escape};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::warn;
+
+pub const DEFAULT_HISTORY_DIR: &str = "load_test_history";
+const INDEX_FILE: &str = "index.json";
+const RUNS_DIR: &str = "runs";
+
+/// Tags attached to a stored run so trends can be traced back to a release
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RunMetadata {
+    pub git_sha: Option<String>,
+    pub build_version: String,
+    pub labels: BTreeMap<String, String>,
+}
+
+impl RunMetadata {
+    /// Collect metadata from the environment.
+    ///
+    /// The git SHA comes from `GIT_SHA` when set (CI), otherwise from
+    /// `git rev-parse HEAD`. Labels come from `LOAD_TEST_LABELS`
+    /// (`env=staging,region=eu`) and are overridden by `extra_labels`.
+    pub fn detect(extra_labels: BTreeMap<String, String>) -> Self {
+        let git_sha = std::env::var("GIT_SHA").ok()
+            .filter(|sha| !sha.is_empty())
+            .or_else(|| {
+                Command::new("git")
+                    .args(["rev-parse", "HEAD"])
+                    .output()
+                    .ok()
+                    .filter(|output| output.status.success())
+                    .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            });
+
+        let mut labels = std::env::var("LOAD_TEST_LABELS")
+            .map(|raw| parse_labels(&raw))
+            .unwrap_or_default();
+        labels.extend(extra_labels);
+
+        Self {
+            git_sha,
+            build_version: env!("CARGO_PKG_VERSION").to_string(),
+            labels,
+        }
+    }
+}
+
+/// Parse comma separated `key=value` pairs, ignoring malformed entries
+pub fn parse_labels(raw: &str) -> BTreeMap<String, String> {
+    raw.split(',')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .filter(|(key, _)| !key.is_empty())
+        .collect()
+}
+
+/// One line of the history index, enough to list runs without loading them
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RunIndexEntry {
+    pub run_id: String,
+    pub file: String,
+    pub test_start_time: DateTime<Local>,
+    pub metadata: RunMetadata,
+    pub overall_requests: usize,
+    pub overall_success_rate: f64,
+    pub overall_rps: f64,
+    pub overall_p95_latency: u64,
+}
+
+/// A directory of JSON runs plus an `index.json` describing them
+pub struct HistoryStore {
+    root: PathBuf,
+}
+
+impl HistoryStore {
+    pub fn open<P: AsRef<Path>>(root: P) -> Result<Self, Box<dyn std::error::Error>> {
+        let root = root.as_ref().to_path_buf();
+        fs::create_dir_all(root.join(RUNS_DIR))?;
+        Ok(Self { root })
+    }
+
+    pub fn load_index(&self) -> Result<Vec<RunIndexEntry>, Box<dyn std::error::Error>> {
+        let index_path = self.root.join(INDEX_FILE);
+        if !index_path.exists() {
+            return Ok(Vec::new());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(index_path)?)?)
+    }
+
+    pub fn load_run(&self, entry: &RunIndexEntry) -> Result<LoadTestReport, Box<dyn std::error::Error>> {
+        Ok(serde_json::from_str(&fs::read_to_string(self.root.join(&entry.file))?)?)
+    }
+
+    /// Store a report as a new run and add it to the index
+    pub fn append(&self, report: &LoadTestReport, metadata: RunMetadata) -> Result<RunIndexEntry, Box<dyn std::error::Error>> {
+        let mut index = self.load_index()?;
+
+        let base_id = report.test_start_time.format("%Y%m%dT%H%M%S").to_string();
+        let mut run_id = base_id.clone();
+        let mut suffix = 1;
+        while index.iter().any(|entry| entry.run_id == run_id) {
+            suffix += 1;
+            run_id = format!("{}-{}", base_id, suffix);
+        }
+
+        let file = format!("{}/{}.json", RUNS_DIR, run_id);
+        let mut run_file = File::create(self.root.join(&file))?;
+        run_file.write_all(serde_json::to_string_pretty(report)?.as_bytes())?;
+
+        let entry = RunIndexEntry {
+            run_id,
+            file,
+            test_start_time: report.test_start_time,
+            metadata,
+            overall_requests: report.overall_requests,
+            overall_success_rate: report.overall_success_rate,
+            overall_rps: report.overall_rps,
+            overall_p95_latency: report.overall_p95_latency,
+        };
+        index.push(entry.clone());
+        index.sort_by_key(|entry| entry.test_start_time);
+
+        let mut index_file = File::create(self.root.join(INDEX_FILE))?;
+        index_file.write_all(serde_json::to_string_pretty(&index)?.as_bytes())?;
+
+        Ok(entry)
+    }
+
+    /// Load every indexed run, skipping files that have gone missing
+    pub fn load_all(&self) -> Result<Vec<(RunIndexEntry, LoadTestReport)>, Box<dyn std::error::Error>> {
+        let mut runs = Vec::new();
+        for entry in self.load_index()? {
+            match self.load_run(&entry) {
+                Ok(report) => runs.push((entry, report)),
+                Err(e) => warn!(run_id = %entry.run_id, error = %e, "Skipping run"),
+            }
+        }
+        Ok(runs)
+    }
+}
+
+/// Per-endpoint aggregate of one run, combined across all scenarios
+struct EndpointTrendPoint {
+    p95_latency: u64,
+    rps: f64,
+    error_rate: f64,
+}
+
+fn endpoint_trend_points(report: &LoadTestReport) -> BTreeMap<String, EndpointTrendPoint> {
+    let mut totals: BTreeMap<String, (usize, usize, f64, u64)> = BTreeMap::new();
+    for scenario in &report.scenarios {
+        for ep in &scenario.endpoints {
+            let entry = totals.entry(ep.endpoint.clone()).or_insert((0, 0, 0.0, 0));
+            entry.0 += ep.total_requests;
+            entry.1 += ep.failed_requests;
+            entry.2 += ep.duration_seconds;
+            // Worst P95 across scenarios; raw samples aren't kept in the report
+            entry.3 = entry.3.max(ep.p95_latency);
+        }
+    }
+
+    totals.into_iter()
+        .map(|(endpoint, (requests, failures, duration, p95_latency))| {
+            let rps = if duration > 0.0 { requests as f64 / duration } else { 0.0 };
+            let error_rate = if requests > 0 {
+                failures as f64 / requests as f64 * 100.0
+            } else {
+                0.0
+            };
+            (endpoint, EndpointTrendPoint { p95_latency, rps, error_rate })
+        })
+        .collect()
+}
+
+fn run_label(entry: &RunIndexEntry) -> String {
+    match &entry.metadata.git_sha {
+        Some(sha) => format!(
+            "{} ({})",
+            entry.test_start_time.format("%Y-%m-%d %H:%M"),
+            sha.chars().take(8).collect::<String>()
+        ),
+        None => entry.test_start_time.format("%Y-%m-%d %H:%M").to_string(),
+    }
+}
+
+/// Render an HTML trend report of P95, RPS and error rate per endpoint over runs.
+/// Charts are inline SVG, so the page needs no scripts or network access.
+pub fn generate_history_html(runs: &[(RunIndexEntry, LoadTestReport)]) -> String {
+    let labels: Vec<String> = runs.iter().map(|(entry, _)| run_label(entry)).collect();
+    let points: Vec<BTreeMap<String, EndpointTrendPoint>> = runs.iter()
+        .map(|(_, report)| endpoint_trend_points(report))
+        .collect();
+
+    let mut endpoints: Vec<&String> = points.iter().flat_map(|p| p.keys()).collect();
+    endpoints.sort();
+    endpoints.dedup();
+
+    let palette = [
+        svg_charts::PRIMARY,
+        svg_charts::SUCCESS,
+        svg_charts::WARNING,
+        svg_charts::DANGER,
+        svg_charts::INFO,
+        svg_charts::SECONDARY,
+    ];
+    let trend_chart = |title: &str, y_title: &str, metric: &dyn Fn(&EndpointTrendPoint) -> f64| -> String {
+        let series: Vec<Series> = endpoints.iter()
+            .enumerate()
+            .map(|(index, endpoint)| Series {
+                label: endpoint.to_string(),
+                values: points.iter().map(|run| run.get(*endpoint).map(metric)).collect(),
+                color: palette[index % palette.len()],
+            })
+            .collect();
+        format!(
+            r#"
+        <div class="chart-container">
+            {}
+        </div>
+"#,
+            svg_charts::line_chart(title, &labels, "Run", (y_title, &series), None)
+        )
+    };
+
+    let run_rows: String = runs.iter()
+        .map(|(entry, _)| {
+            let labels = entry.metadata.labels.iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!(r#"
+            <tr>
+                <td>{}</td>
+                <td>{}</td>
+                <td>{}</td>
+                <td>{}</td>
+                <td>{}</td>
+                <td>{}</td>
+                <td>{:.2}%</td>
+                <td>{:.2}</td>
+                <td>{}ms</td>
+            </tr>"#,
+                escape(&entry.run_id),
+                entry.test_start_time.format("%Y-%m-%d %H:%M:%S"),
+                escape(entry.metadata.git_sha.as_deref().unwrap_or("-")),
+                escape(&entry.metadata.build_version),
+                escape(&labels),
+                entry.overall_requests,
+                entry.overall_success_rate,
+                entry.overall_rps,
+                entry.overall_p95_latency
+            )
+        })
+        .collect();
+
+    format!(r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="UTF-8">
+    <title>Load Test History</title>
+    <style>
+        body {{ font-family: Arial, sans-serif; margin: 40px; line-height: 1.6; }}
+        .header {{ text-align: center; border-bottom: 2px solid #333; padding-bottom: 20px; }}
+        .summary-table {{ border-collapse: collapse; width: 100%; margin: 20px 0; font-size: 12px; }}
+        .summary-table th, .summary-table td {{ border: 1px solid #ddd; padding: 8px; text-align: left; }}
+        .summary-table th {{ background-color: #f2f2f2; }}
+        .section {{ margin: 30px 0; }}
+        .chart-container {{ border: 1px solid #ddd; margin: 20px 0; padding: 20px; background-color: #f9f9f9; }}
+    </style>
+</head>
+<body>
+    <div class="header">
+        <h1>Load Test History</h1>
+        <p>Generated on: {}</p>
+        <p>Runs: {}</p>
+    </div>
+
+    <div class="section">
+        <h2>1. Runs</h2>
+        <table class="summary-table">
+            <tr>
+                <th>Run ID</th>
+                <th>Start Time</th>
+                <th>Git SHA</th>
+                <th>Build Version</th>
+                <th>Labels</th>
+                <th>Total Requests</th>
+                <th>Success Rate</th>
+                <th>RPS</th>
+                <th>P95 Latency</th>
+            </tr>
+            {}
+        </table>
+    </div>
+
+    <div class="section">
+        <h2>2. Endpoint Trends</h2>
+{}{}{}    </div>
+</body>
+</html>"#,
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        runs.len(),
+        run_rows,
+        trend_chart("P95 Latency per Endpoint (worst scenario)", "Latency (milliseconds)", &|p| p.p95_latency as f64),
+        trend_chart("Throughput per Endpoint (RPS)", "Requests Per Second", &|p| p.rps),
+        trend_chart("Error Rate per Endpoint (%)", "Error Rate (%)", &|p| p.error_rate)
+    )
+}
+
+/// Write the trend report for every run in `store` to `output_path`
+pub fn save_history_report<P: AsRef<Path>>(store: &HistoryStore, output_path: P) -> Result<usize, Box<dyn std::error::Error>> {
+    let runs = store.load_all()?;
+    let mut html_file = File::create(output_path)?;
+    html_file.write_all(generate_history_html(&runs).as_bytes())?;
+    Ok(runs.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::fixtures::{endpoint, report, scenario};
+
+    fn metadata(git_sha: Option<&str>, labels: &str) -> RunMetadata {
+        RunMetadata {
+            git_sha: git_sha.map(str::to_string),
+            build_version: "1.0.0".to_string(),
+            labels: parse_labels(labels),
+        }
+    }
+
+    fn temp_store(name: &str) -> (PathBuf, HistoryStore) {
+        let root = std::env::temp_dir().join(format!("load-test-rs-history-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let store = HistoryStore::open(&root).unwrap();
+        (root, store)
+    }
+
+    #[test]
+    fn parses_labels_and_skips_malformed_pairs() {
+        let labels = parse_labels(" env = staging ,broken,=empty,region=eu");
+        assert_eq!(labels.len(), 2);
+        assert_eq!(labels["env"], "staging");
+        assert_eq!(labels["region"], "eu");
+    }
+
+    #[test]
+    fn appended_runs_get_unique_ids_and_load_back() {
+        let (root, store) = temp_store("append");
+        let run = report(vec![scenario(10, vec![endpoint("/items", 100, 5, 40)])]);
+        let first = store.append(&run, metadata(Some("abc"), "env=ci")).unwrap();
+        let second = store.append(&run, metadata(None, "")).unwrap();
+        assert_eq!(second.run_id, format!("{}-2", first.run_id));
+
+        let runs = store.load_all().unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].1.overall_requests, 100);
+        assert_eq!(runs[0].0.metadata.labels["env"], "ci");
+
+        // A run file that has gone missing is skipped
+        fs::remove_file(root.join(&first.file)).unwrap();
+        assert_eq!(store.load_all().unwrap().len(), 1);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn run_labels_shorten_the_sha_by_characters() {
+        let (root, store) = temp_store("labels");
+        let run = report(vec![scenario(10, vec![endpoint("/items", 10, 0, 40)])]);
+        let mut entry = store.append(&run, metadata(Some("0123456789abcdef"), "")).unwrap();
+        assert!(run_label(&entry).ends_with("(01234567)"));
+        entry.metadata.git_sha = Some("ßßßßßßßßßß".to_string());
+        assert!(run_label(&entry).ends_with("(ßßßßßßßß)"));
+        entry.metadata.git_sha = Some("abc".to_string());
+        assert!(run_label(&entry).ends_with("(abc)"));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn endpoint_trends_combine_scenarios() {
+        let run = report(vec![
+            scenario(10, vec![endpoint("/items", 100, 10, 40)]),
+            scenario(20, vec![endpoint("/items", 100, 30, 90)]),
+        ]);
+        let points = endpoint_trend_points(&run);
+        let items = &points["/items"];
+        assert_eq!(items.p95_latency, 90);
+        assert_eq!(items.rps, 50.0);
+        assert_eq!(items.error_rate, 20.0);
+    }
+
+    #[test]
+    fn history_page_escapes_names_and_needs_no_scripts() {
+        let (root, store) = temp_store("html");
+        let run = report(vec![scenario(10, vec![endpoint("/search?q=<script>\"x\"", 10, 1, 40)])]);
+        store.append(&run, metadata(Some("<b>sha</b>"), "team=<img src=x>")).unwrap();
+        let html = generate_history_html(&store.load_all().unwrap());
+        fs::remove_dir_all(root).unwrap();
+
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("cdn.jsdelivr.net"));
+        assert!(!html.contains("<img"));
+        assert!(!html.contains("<b>sha"));
+        assert!(html.contains("/search?q=&lt;script&gt;&quot;x&quot;"));
+        assert!(html.contains("team=&lt;img src=x&gt;"));
+        assert_eq!(html.matches("<svg").count(), 3);
+    }
+
+    #[test]
+    fn empty_history_renders() {
+        let html = generate_history_html(&[]);
+        assert!(html.contains("Runs: 0"));
+    }
+}
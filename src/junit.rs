@@ -0,0 +1,224 @@
+use crate::models::{EndpointResult, LoadTestReport, ScenarioResult};
+use crate::reports::SCALING_THRESHOLDS;
+
+/// Pass/fail criteria the JUnit reporter checks per scenario and per endpoint
+#[derive(Debug, Clone)]
+pub struct Thresholds {
+    pub min_success_rate: f64,        // Percent
+    pub max_p95_latency: Option<u64>, // Milliseconds
+    pub max_p99_latency: Option<u64>, // Milliseconds
+    pub min_rps: Option<f64>,         // Scenario-wide only
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            min_success_rate: SCALING_THRESHOLDS.min_success_rate,
+            max_p95_latency: None,
+            max_p99_latency: None,
+            min_rps: None,
+        }
+    }
+}
+
+/// One `<testcase>`: a measured value compared against its threshold
+struct Check {
+    name: String,
+    measured: String,
+    expected: String,
+    passed: bool,
+}
+
+impl Check {
+    fn at_least(metric: &str, measured: f64, minimum: f64, unit: &str) -> Self {
+        Self {
+            name: format!("{} >= {:.2}{}", metric, minimum, unit),
+            measured: format!("{:.2}{}", measured, unit),
+            expected: format!(">= {:.2}{}", minimum, unit),
+            passed: measured >= minimum,
+        }
+    }
+
+    fn at_most(metric: &str, measured: u64, maximum: u64, unit: &str) -> Self {
+        Self {
+            name: format!("{} <= {}{}", metric, maximum, unit),
+            measured: format!("{}{}", measured, unit),
+            expected: format!("<= {}{}", maximum, unit),
+            passed: measured <= maximum,
+        }
+    }
+}
+
+impl Thresholds {
+    fn scenario_checks(&self, scenario: &ScenarioResult) -> Vec<Check> {
+        let mut checks = vec![Check::at_least("success_rate", scenario.success_rate, self.min_success_rate, "%")];
+        if let Some(max) = self.max_p95_latency {
+            checks.push(Check::at_most("p95_latency", scenario.p95_latency, max, "ms"));
+        }
+        if let Some(max) = self.max_p99_latency {
+            checks.push(Check::at_most("p99_latency", scenario.p99_latency, max, "ms"));
+        }
+        if let Some(min) = self.min_rps {
+            checks.push(Check::at_least("rps", scenario.rps, min, " req/sec"));
+        }
+        checks
+    }
+
+    fn endpoint_checks(&self, endpoint: &EndpointResult) -> Vec<Check> {
+        let mut checks = vec![Check::at_least("success_rate", endpoint.success_rate, self.min_success_rate, "%")];
+        if let Some(max) = self.max_p95_latency {
+            checks.push(Check::at_most("p95_latency", endpoint.p95_latency, max, "ms"));
+        }
+        if let Some(max) = self.max_p99_latency {
+            checks.push(Check::at_most("p99_latency", endpoint.p99_latency, max, "ms"));
+        }
+        checks
+    }
+}
+
+impl LoadTestReport {
+    /// JUnit XML with one `<testsuite>` per scenario. Scenario-wide thresholds are
+    /// testcases with classname `scenario`; each endpoint's assertions use the
+    /// endpoint path as classname. Failures carry the measured and expected values.
+    pub fn generate_junit_xml(&self, thresholds: &Thresholds) -> String {
+        let mut suites = String::new();
+        let mut total_tests = 0;
+        let mut total_failures = 0;
+
+        for (index, scenario) in self.scenarios.iter().enumerate() {
+            let mut cases = String::new();
+            let mut tests = 0;
+            let mut failures = 0;
+
+            let mut add = |classname: &str, time: f64, checks: Vec<Check>| {
+                for check in checks {
+                    tests += 1;
+                    cases.push_str(&format!(
+                        "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\"",
+                        escape_xml(classname),
+                        escape_xml(&check.name),
+                        time
+                    ));
+                    if check.passed {
+                        cases.push_str("/>\n");
+                    } else {
+                        failures += 1;
+                        cases.push_str(&format!(
+                            ">\n      <failure type=\"threshold\" message=\"measured {}, expected {}\">measured: {}\nexpected: {}</failure>\n    </testcase>\n",
+                            escape_xml(&check.measured),
+                            escape_xml(&check.expected),
+                            escape_xml(&check.measured),
+                            escape_xml(&check.expected)
+                        ));
+                    }
+                }
+            };
+
+            add("scenario", scenario.duration_seconds, thresholds.scenario_checks(scenario));
+            for endpoint in &scenario.endpoints {
+                add(&endpoint.endpoint, endpoint.duration_seconds, thresholds.endpoint_checks(endpoint));
+            }
+
+            suites.push_str(&format!(
+                r#"  <testsuite name="Scenario {} - Concurrency: {}" tests="{}" failures="{}" errors="0" time="{:.3}" timestamp="{}">
+    <properties>
+      <property name="base_url" value="{}"/>
+      <property name="concurrency" value="{}"/>
+      <property name="total_requests" value="{}"/>
+    </properties>
+{}  </testsuite>
+"#,
+                index + 1,
+                scenario.concurrency,
+                tests,
+                failures,
+                scenario.duration_seconds,
+                self.test_start_time.format("%Y-%m-%dT%H:%M:%S"),
+                escape_xml(&self.base_url),
+                scenario.concurrency,
+                scenario.total_requests,
+                cases
+            ));
+            total_tests += tests;
+            total_failures += failures;
+        }
+
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites name=\"load-test\" tests=\"{}\" failures=\"{}\" errors=\"0\" time=\"{:.3}\">\n{}</testsuites>\n",
+            total_tests, total_failures, self.total_duration_seconds, suites
+        )
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::fixtures::{endpoint, report, scenario};
+
+    fn thresholds() -> Thresholds {
+        Thresholds {
+            min_success_rate: 95.0,
+            max_p95_latency: Some(200),
+            max_p99_latency: None,
+            min_rps: Some(50.0),
+        }
+    }
+
+    #[test]
+    fn checks_compare_against_thresholds() {
+        let slow = endpoint("/slow", 100, 10, 250);
+        let checks = thresholds().endpoint_checks(&slow);
+        assert_eq!(checks.len(), 2);
+        assert_eq!(checks[0].name, "success_rate >= 95.00%");
+        assert!(!checks[0].passed);
+        assert_eq!((checks[1].measured.as_str(), checks[1].expected.as_str()), ("250ms", "<= 200ms"));
+        assert!(!checks[1].passed);
+
+        // Boundaries pass, and rps is only checked scenario-wide
+        let checks = thresholds().scenario_checks(&scenario(10, vec![endpoint("/ok", 100, 5, 200)]));
+        assert_eq!(checks.len(), 3);
+        assert!(checks.iter().all(|check| check.passed));
+        assert_eq!(checks[2].name, "rps >= 50.00 req/sec");
+    }
+
+    #[test]
+    fn junit_counts_tests_and_failures() {
+        let report = report(vec![
+            scenario(10, vec![endpoint("/ok", 100, 0, 50)]),
+            scenario(50, vec![endpoint("/ok", 100, 0, 50), endpoint("/slow", 100, 10, 250)]),
+        ]);
+        let xml = report.generate_junit_xml(&thresholds());
+
+        // Scenario 2 fails its p95 check and /slow its success rate and p95 checks
+        assert!(xml.starts_with(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites name=\"load-test\" tests=\"12\" failures=\"3\""
+        ));
+        assert!(xml.contains("<testsuite name=\"Scenario 1 - Concurrency: 10\" tests=\"5\" failures=\"0\""));
+        assert!(xml.contains("<testsuite name=\"Scenario 2 - Concurrency: 50\" tests=\"7\" failures=\"3\""));
+        assert!(xml.contains(
+            "<testcase classname=\"/slow\" name=\"p95_latency &lt;= 200ms\" time=\"2.000\">\n      \
+             <failure type=\"threshold\" message=\"measured 250ms, expected &lt;= 200ms\">"
+        ));
+        assert_eq!(xml.matches("<testcase").count(), 12);
+        assert_eq!(xml.matches("<failure").count(), 3);
+    }
+
+    #[test]
+    fn junit_escapes_names_and_properties() {
+        let mut report = report(vec![scenario(1, vec![endpoint("/search?q=\"a\"&b='<c>'", 10, 0, 5)])]);
+        report.base_url = "http://api.test/?a=1&b=2".to_string();
+        let xml = report.generate_junit_xml(&Thresholds::default());
+
+        assert!(xml.contains("classname=\"/search?q=&quot;a&quot;&amp;b=&apos;&lt;c&gt;&apos;\""));
+        assert!(xml.contains("<property name=\"base_url\" value=\"http://api.test/?a=1&amp;b=2\"/>"));
+        assert_eq!(escape_xml("a & b"), "a &amp; b");
+    }
+}
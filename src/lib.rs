@@ -1,11 +1,51 @@
 pub mod config;
+pub mod cookies;
+pub mod dashboard;
+pub mod export;
+pub mod graphql;
+pub mod grpc;
+pub mod history;
+pub mod http_client;
+pub mod junit;
+pub mod live;
+pub mod live_server;
+pub mod logging;
 pub mod metrics;
 pub mod models;
+pub mod prometheus;
 pub mod reports;
+pub mod request_log;
+pub mod resolve;
+pub mod sinks;
+pub mod sse;
+pub mod svg_charts;
+pub mod templates;
+pub mod tls;
+pub mod trace_context;
+pub mod websocket;
 pub mod load_tester;
 
 pub use config::*;
+pub use dashboard::*;
+pub use export::*;
+pub use graphql::*;
+pub use grpc::*;
+pub use history::*;
+pub use http_client::*;
+pub use junit::*;
+pub use live::*;
+pub use live_server::*;
+pub use logging::*;
 pub use metrics::*;
 pub use models::*;
+pub use prometheus::*;
 pub use reports::*;
+pub use request_log::*;
+pub use resolve::*;
+pub use sinks::*;
+pub use sse::*;
+pub use templates::*;
+pub use tls::*;
+pub use trace_context::*;
+pub use websocket::*;
 pub use load_tester::*;
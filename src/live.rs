@@ -0,0 +1,516 @@
+use crate::metrics::LatencyMetrics;
+use crate::models::{ErrorClass, LoadTestReport};
+use crate::sinks::IntervalAggregate;
+use chrono::Local;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Window for rolling RPS and latency percentiles
+pub const ROLLING_WINDOW: Duration = Duration::from_secs(10);
+const SPARKLINE_POINTS: usize = 120;
+
+/// Upper bounds (ms) of the cumulative latency histogram buckets
+pub const LATENCY_BUCKETS_MS: [u64; 13] = [5, 10, 25, 50, 100, 250, 500, 1000, 2500, 5000, 10000, 30000, 60000];
+
+/// Cumulative latency histogram over the whole run
+#[derive(Clone, Default)]
+pub struct LatencyHistogram {
+    pub bucket_counts: [u64; LATENCY_BUCKETS_MS.len()], // Requests at or below each bound
+    pub count: u64,
+    pub sum_ms: u64,
+}
+
+impl LatencyHistogram {
+    fn observe(&mut self, latency_ms: u64) {
+        for (bound, count) in LATENCY_BUCKETS_MS.iter().zip(self.bucket_counts.iter_mut()) {
+            if latency_ms <= *bound {
+                *count += 1;
+            }
+        }
+        self.count += 1;
+        self.sum_ms += latency_ms;
+    }
+}
+
+/// Completed requests for one endpoint, status and error class; status 0 means no response
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RequestCountKey {
+    pub endpoint: String,
+    pub status: u16,
+    pub grpc_code: Option<u16>, // gRPC status code on gRPC rows
+    pub error_class: Option<ErrorClass>,
+}
+
+/// Flags the UI sets and the engine polls between request submissions
+#[derive(Default)]
+pub struct RunControl {
+    paused: AtomicBool,
+    skip_scenario: AtomicBool,
+    abort: AtomicBool,
+}
+
+impl RunControl {
+    pub fn toggle_pause(&self) {
+        self.paused.fetch_xor(true, Ordering::SeqCst);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Stop submitting requests for the current scenario and move to the next
+    pub fn skip_scenario(&self) {
+        self.skip_scenario.store(true, Ordering::SeqCst);
+    }
+
+    /// Stop submitting requests and finish the run with what was collected
+    pub fn abort(&self) {
+        self.abort.store(true, Ordering::SeqCst);
+        self.paused.store(false, Ordering::SeqCst);
+    }
+
+    pub fn is_aborted(&self) -> bool {
+        self.abort.load(Ordering::SeqCst)
+    }
+
+    pub fn should_stop_scenario(&self) -> bool {
+        self.skip_scenario.load(Ordering::SeqCst) || self.is_aborted()
+    }
+
+    pub(crate) fn clear_skip(&self) {
+        self.skip_scenario.store(false, Ordering::SeqCst);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStage {
+    Starting,
+    Running,
+    Paused,
+    Skipping,
+    Aborting,
+    Finished,
+}
+
+struct EndpointActivity {
+    endpoint: String,
+    completed: usize,
+    errors: usize,
+    per_second: VecDeque<(u64, u64)>, // (second since run start, completions), seconds without completions omitted
+}
+
+#[derive(Default)]
+struct LiveState {
+    run_started: Option<Instant>,
+    finished: bool,
+    scenario_index: usize,
+    scenario_count: usize,
+    concurrency: usize,
+    current_endpoint: String,
+    planned_requests: usize,
+    completed_requests: usize,
+    failed_requests: usize,
+    in_flight: usize,
+    recent: VecDeque<(Instant, u64)>, // (completion time, latency ms)
+    errors_by_class: BTreeMap<ErrorClass, usize>,
+    endpoints: Vec<EndpointActivity>,
+    target_rps: f64,
+    request_counts: BTreeMap<RequestCountKey, u64>,
+    latency_histograms: BTreeMap<String, LatencyHistogram>,
+    interval_aggregates: Option<BTreeMap<(usize, String), (LatencyMetrics, usize)>>, // (concurrency, endpoint) -> (latencies, errors); None unless metrics sinks drain it
+    report: Option<Arc<LoadTestReport>>,
+    report_version: usize,
+}
+
+/// Point-in-time view of a run for dashboards
+#[derive(Clone, Serialize)]
+pub struct LiveSnapshot {
+    pub stage: RunStage,
+    pub scenario_index: usize, // 1-based, 0 before the first scenario
+    pub scenario_count: usize,
+    pub concurrency: usize,
+    pub current_endpoint: String,
+    pub elapsed_seconds: f64,
+    pub remaining_seconds: Option<f64>,
+    pub planned_requests: usize,
+    pub completed_requests: usize,
+    pub failed_requests: usize,
+    pub in_flight: usize, // Requests awaiting a response plus open WebSocket connections and event stream subscriptions
+    pub rolling_rps: f64,
+    pub target_rps: f64, // Submission ceiling from request pacing for the current endpoint
+    pub rolling_p50_latency: u64,
+    pub rolling_p95_latency: u64,
+    pub rolling_p99_latency: u64,
+    pub errors_by_class: BTreeMap<String, usize>,
+    pub endpoints: Vec<EndpointSnapshot>,
+    pub report_version: usize, // Bumped whenever a newer (provisional or final) report is published
+}
+
+#[derive(Clone, Serialize)]
+pub struct EndpointSnapshot {
+    pub endpoint: String,
+    pub completed: usize,
+    pub errors: usize,
+    pub per_second: Vec<u64>, // Completions per second, oldest first, 0 for idle seconds, for sparklines
+}
+
+/// Shared handle to the live state of a run and its controls
+#[derive(Clone, Default)]
+pub struct LiveMonitor {
+    state: Arc<Mutex<LiveState>>,
+    control: Arc<RunControl>,
+}
+
+impl LiveMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn control(&self) -> &RunControl {
+        &self.control
+    }
+
+    pub(crate) fn start_run(&self, scenario_count: usize, planned_requests: usize) {
+        let mut state = self.state.lock().unwrap();
+        *state = LiveState {
+            run_started: Some(Instant::now()),
+            scenario_count,
+            planned_requests,
+            ..LiveState::default()
+        };
+    }
+
+    pub(crate) fn start_scenario(&self, index: usize, concurrency: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.scenario_index = index + 1;
+        state.concurrency = concurrency;
+        self.control.clear_skip();
+    }
+
+    pub(crate) fn start_endpoint(&self, endpoint: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.current_endpoint = endpoint.to_string();
+        if !state.endpoints.iter().any(|activity| activity.endpoint == endpoint) {
+            state.endpoints.push(EndpointActivity {
+                endpoint: endpoint.to_string(),
+                completed: 0,
+                errors: 0,
+                per_second: VecDeque::new(),
+            });
+        }
+    }
+
+    pub(crate) fn set_target_rps(&self, target_rps: f64) {
+        self.state.lock().unwrap().target_rps = target_rps;
+    }
+
+    /// A request was sent, or a WebSocket connection or event stream subscription opened
+    pub(crate) fn request_started(&self) {
+        self.state.lock().unwrap().in_flight += 1;
+    }
+
+    /// Pairs with `request_started` once the request completes or the connection or subscription closes,
+    /// however it ended
+    pub(crate) fn request_ended(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.in_flight = state.in_flight.saturating_sub(1);
+    }
+
+    pub(crate) fn request_finished(
+        &self,
+        endpoint: &str,
+        status: Option<u16>,
+        grpc_code: Option<u16>,
+        latency_ms: u64,
+        error_class: Option<ErrorClass>,
+    ) {
+        self.record_completion(endpoint, status, grpc_code, Some(latency_ms), error_class);
+    }
+
+    /// A request that failed before anything was sent, counted as an error without a latency
+    pub(crate) fn request_not_sent(&self, endpoint: &str, error_class: ErrorClass) {
+        self.record_completion(endpoint, None, None, None, Some(error_class));
+    }
+
+    fn record_completion(
+        &self,
+        endpoint: &str,
+        status: Option<u16>,
+        grpc_code: Option<u16>,
+        latency_ms: Option<u64>,
+        error_class: Option<ErrorClass>,
+    ) {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        let second = state.run_started.map(|start| now.duration_since(start).as_secs()).unwrap_or(0);
+
+        state.completed_requests += 1;
+        if let Some(class) = error_class {
+            state.failed_requests += 1;
+            *state.errors_by_class.entry(class).or_insert(0) += 1;
+        }
+        let key = RequestCountKey {
+            endpoint: endpoint.to_string(),
+            status: status.unwrap_or(0),
+            grpc_code,
+            error_class,
+        };
+        *state.request_counts.entry(key).or_insert(0) += 1;
+        // Interval aggregates count requests by their latencies, so unsent requests stay out of them
+        if let Some(latency_ms) = latency_ms {
+            state.recent.push_back((now, latency_ms));
+            prune_recent(&mut state.recent, now);
+            state.latency_histograms.entry(endpoint.to_string()).or_default().observe(latency_ms);
+            let concurrency = state.concurrency;
+            if let Some(intervals) = state.interval_aggregates.as_mut() {
+                let (latencies, errors) = intervals.entry((concurrency, endpoint.to_string())).or_default();
+                latencies.add_latency(latency_ms);
+                if error_class.is_some() {
+                    *errors += 1;
+                }
+            }
+        }
+
+        if let Some(activity) = state.endpoints.iter_mut().find(|activity| activity.endpoint == endpoint) {
+            activity.completed += 1;
+            if error_class.is_some() {
+                activity.errors += 1;
+            }
+            match activity.per_second.back_mut() {
+                Some((last_second, count)) if *last_second == second => *count += 1,
+                _ => {
+                    activity.per_second.push_back((second, 1));
+                    while activity.per_second.front()
+                        .is_some_and(|&(first, _)| first + SPARKLINE_POINTS as u64 <= second)
+                    {
+                        activity.per_second.pop_front();
+                    }
+                }
+            }
+        }
+    }
+
+    pub(crate) fn publish_report(&self, report: LoadTestReport) {
+        let mut state = self.state.lock().unwrap();
+        state.report = Some(Arc::new(report));
+        state.report_version += 1;
+    }
+
+    /// Latest published report: scenarios completed so far, or the final report
+    pub fn latest_report(&self) -> Option<Arc<LoadTestReport>> {
+        self.state.lock().unwrap().report.clone()
+    }
+
+    /// Completed request counters since the run started
+    pub fn request_counts(&self) -> BTreeMap<RequestCountKey, u64> {
+        self.state.lock().unwrap().request_counts.clone()
+    }
+
+    /// Per-endpoint latency histograms since the run started
+    pub fn latency_histograms(&self) -> BTreeMap<String, LatencyHistogram> {
+        self.state.lock().unwrap().latency_histograms.clone()
+    }
+
+    pub(crate) fn enable_interval_aggregates(&self) {
+        self.state.lock().unwrap().interval_aggregates.get_or_insert_with(BTreeMap::new);
+    }
+
+    /// Aggregates of the requests completed since the previous call, per concurrency and endpoint
+    pub(crate) fn take_interval_aggregates(&self, run_id: &str, interval_seconds: f64) -> Vec<IntervalAggregate> {
+        let intervals = match self.state.lock().unwrap().interval_aggregates.as_mut() {
+            Some(intervals) => std::mem::take(intervals),
+            None => return Vec::new(),
+        };
+        let timestamp = Local::now();
+
+        intervals.into_iter()
+            .map(|((concurrency, endpoint), (latencies, errors))| {
+                let requests = latencies.latencies.len();
+                IntervalAggregate {
+                    timestamp,
+                    interval_seconds,
+                    run_id: run_id.to_string(),
+                    concurrency,
+                    endpoint,
+                    requests,
+                    errors,
+                    rps: if interval_seconds > 0.0 { requests as f64 / interval_seconds } else { 0.0 },
+                    mean_latency: latencies.mean(),
+                    p50_latency: latencies.percentile(50.0),
+                    p95_latency: latencies.percentile(95.0),
+                    p99_latency: latencies.percentile(99.0),
+                }
+            })
+            .collect()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.state.lock().unwrap().finished
+    }
+
+    pub(crate) fn finish_run(&self) {
+        self.state.lock().unwrap().finished = true;
+    }
+
+    pub fn snapshot(&self) -> LiveSnapshot {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        prune_recent(&mut state.recent, now);
+
+        // Idle seconds up to now count as zeros while the run goes on
+        let current_second = state.run_started
+            .filter(|_| !state.finished)
+            .map(|start| now.duration_since(start).as_secs());
+        let elapsed_seconds = state.run_started
+            .map(|start| now.duration_since(start).as_secs_f64())
+            .unwrap_or(0.0);
+        let window = ROLLING_WINDOW.as_secs_f64().min(elapsed_seconds).max(1.0);
+        let rolling_rps = state.recent.len() as f64 / window;
+
+        let mut rolling = LatencyMetrics::new();
+        for &(_, latency) in &state.recent {
+            rolling.add_latency(latency);
+        }
+
+        // Remaining time at the average rate so far
+        let remaining_seconds = if state.completed_requests > 0 && elapsed_seconds > 0.0 && !state.finished {
+            let rate = state.completed_requests as f64 / elapsed_seconds;
+            Some(state.planned_requests.saturating_sub(state.completed_requests) as f64 / rate)
+        } else {
+            None
+        };
+
+        let stage = if state.finished {
+            RunStage::Finished
+        } else if self.control.is_aborted() {
+            RunStage::Aborting
+        } else if self.control.should_stop_scenario() {
+            RunStage::Skipping
+        } else if self.control.is_paused() {
+            RunStage::Paused
+        } else if state.scenario_index == 0 {
+            RunStage::Starting
+        } else {
+            RunStage::Running
+        };
+
+        LiveSnapshot {
+            stage,
+            scenario_index: state.scenario_index,
+            scenario_count: state.scenario_count,
+            concurrency: state.concurrency,
+            current_endpoint: state.current_endpoint.clone(),
+            elapsed_seconds,
+            remaining_seconds,
+            planned_requests: state.planned_requests,
+            completed_requests: state.completed_requests,
+            failed_requests: state.failed_requests,
+            in_flight: state.in_flight,
+            rolling_rps,
+            target_rps: state.target_rps,
+            rolling_p50_latency: rolling.percentile(50.0),
+            rolling_p95_latency: rolling.percentile(95.0),
+            rolling_p99_latency: rolling.percentile(99.0),
+            errors_by_class: state.errors_by_class.iter()
+                .map(|(class, count)| (class.as_str().to_string(), *count))
+                .collect(),
+            endpoints: state.endpoints.iter()
+                .map(|activity| EndpointSnapshot {
+                    endpoint: activity.endpoint.clone(),
+                    completed: activity.completed,
+                    errors: activity.errors,
+                    per_second: per_second_series(&activity.per_second, current_second),
+                })
+                .collect(),
+            report_version: state.report_version,
+        }
+    }
+}
+
+fn prune_recent(recent: &mut VecDeque<(Instant, u64)>, now: Instant) {
+    while let Some(&(completed_at, _)) = recent.front() {
+        if now.duration_since(completed_at) <= ROLLING_WINDOW {
+            break;
+        }
+        recent.pop_front();
+    }
+}
+
+/// Completions per second over the last `SPARKLINE_POINTS` seconds up to `until` (default: the latest
+/// completion), with zeros for the seconds nothing completed
+fn per_second_series(per_second: &VecDeque<(u64, u64)>, until: Option<u64>) -> Vec<u64> {
+    let (Some(&(first, _)), Some(&(last, _))) = (per_second.front(), per_second.back()) else {
+        return Vec::new();
+    };
+    let end = until.unwrap_or(last).max(last);
+    let start = first.max((end + 1).saturating_sub(SPARKLINE_POINTS as u64));
+    let mut series = vec![0; (end - start + 1) as usize];
+    for &(second, count) in per_second {
+        if second >= start {
+            series[(second - start) as usize] = count;
+        }
+    }
+    series
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn in_flight_counts_open_connections_until_they_end() {
+        let monitor = LiveMonitor::new();
+        monitor.start_run(1, 10);
+        monitor.start_endpoint("WS /chat");
+        monitor.request_started();
+        monitor.request_started();
+        // Messages on an open connection complete without ending it
+        monitor.request_finished("WS /chat", Some(101), None, 5, None);
+        monitor.request_finished("WS /chat", Some(101), None, 7, None);
+        assert_eq!(monitor.snapshot().in_flight, 2);
+
+        monitor.request_finished("WS /chat", None, None, 30, Some(ErrorClass::Connect));
+        monitor.request_ended();
+        monitor.request_ended();
+        let snapshot = monitor.snapshot();
+        assert_eq!(snapshot.in_flight, 0);
+        assert_eq!(snapshot.completed_requests, 3);
+        assert_eq!(snapshot.failed_requests, 1);
+
+        monitor.request_ended();
+        assert_eq!(monitor.snapshot().in_flight, 0);
+    }
+
+    #[test]
+    fn unsent_requests_count_as_errors_without_a_latency() {
+        let monitor = LiveMonitor::new();
+        monitor.start_run(1, 2);
+        monitor.start_endpoint("WS /chat");
+        monitor.request_finished("WS /chat", Some(101), None, 40, None);
+        monitor.request_not_sent("WS /chat", ErrorClass::Other);
+
+        let snapshot = monitor.snapshot();
+        assert_eq!((snapshot.completed_requests, snapshot.failed_requests), (2, 1));
+        assert_eq!(snapshot.rolling_p50_latency, 40);
+        assert_eq!(monitor.latency_histograms()["WS /chat"].count, 1);
+        assert_eq!(monitor.request_counts().values().sum::<u64>(), 2);
+    }
+
+    #[test]
+    fn sparkline_fills_idle_seconds_with_zeros() {
+        let per_second = VecDeque::from([(3, 4), (4, 2), (7, 1)]);
+        assert_eq!(per_second_series(&per_second, None), vec![4, 2, 0, 0, 1]);
+        assert_eq!(per_second_series(&per_second, Some(9)), vec![4, 2, 0, 0, 1, 0, 0]);
+        assert!(per_second_series(&VecDeque::new(), Some(9)).is_empty());
+
+        let late = VecDeque::from([(10, 1), (200, 3)]);
+        let series = per_second_series(&late, Some(205));
+        assert_eq!(series.len(), SPARKLINE_POINTS);
+        assert_eq!(series[SPARKLINE_POINTS - 6], 3);
+        assert_eq!(series.iter().sum::<u64>(), 3);
+    }
+}
@@ -0,0 +1,221 @@
+use crate::live::LiveMonitor;
+use crate::models::LoadTestReport;
+use crate::prometheus::render_prometheus_metrics;
+use crate::reports::ReportOptions;
+use crate::svg_charts::escape;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+const EVENT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Serve a live version of the HTML report on `addr` while the run progresses.
+///
+/// `/` renders the latest published report (scenarios completed so far) with a
+/// live panel fed by Server-Sent Events from `/events`; the page reloads when a
+/// scenario completes. Once the run has finished `/` serves the final report
+/// without the live panel. `/snapshot` returns the current state as JSON and
+/// `/metrics` exposes counters and latency histograms for Prometheus to scrape.
+/// Pages are rendered with the run's `options`, like the saved `index.html`.
+pub fn serve_live_dashboard(addr: &str, monitor: LiveMonitor, options: ReportOptions) -> io::Result<JoinHandle<()>> {
+    let listener = TcpListener::bind(addr)?;
+    let options = Arc::new(options);
+    Ok(thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let monitor = monitor.clone();
+            let options = Arc::clone(&options);
+            thread::spawn(move || {
+                // Browsers drop connections freely; nothing useful to do on error
+                let _ = handle_connection(stream, &monitor, &options);
+            });
+        }
+    }))
+}
+
+fn handle_connection(stream: TcpStream, monitor: &LiveMonitor, options: &ReportOptions) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    // Drain the headers; none of them change the response
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+    let path = path.split('?').next().unwrap_or("/");
+    match path {
+        "/" | "/index.html" => write_response(stream, "200 OK", "text/html; charset=utf-8", &render_page(monitor, options)),
+        "/snapshot" => {
+            let json = serde_json::to_string(&monitor.snapshot()).unwrap_or_else(|_| "{}".to_string());
+            write_response(stream, "200 OK", "application/json", &json)
+        }
+        "/events" => stream_events(stream, monitor),
+        "/metrics" => write_response(
+            stream,
+            "200 OK",
+            "text/plain; version=0.0.4; charset=utf-8",
+            &render_prometheus_metrics(monitor),
+        ),
+        _ => write_response(stream, "404 Not Found", "text/plain", "Not Found"),
+    }
+}
+
+fn write_response(mut stream: TcpStream, status: &str, content_type: &str, body: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    stream.write_all(body.as_bytes())?;
+    stream.flush()
+}
+
+fn stream_events(mut stream: TcpStream, monitor: &LiveMonitor) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n"
+    )?;
+    loop {
+        let snapshot = monitor.snapshot();
+        let json = serde_json::to_string(&snapshot).unwrap_or_else(|_| "{}".to_string());
+        write!(stream, "data: {}\n\n", json)?;
+        stream.flush()?;
+        if monitor.is_finished() {
+            return Ok(());
+        }
+        thread::sleep(EVENT_INTERVAL);
+    }
+}
+
+fn render_page(monitor: &LiveMonitor, options: &ReportOptions) -> String {
+    let render = |report: &LoadTestReport| {
+        report.generate_html_with_options(options)
+            .unwrap_or_else(|e| message_page(&format!("Error rendering the report: {}", escape(&e.to_string()))))
+    };
+    let report = monitor.latest_report();
+    if monitor.is_finished()
+        && let Some(report) = report
+    {
+        return render(&report);
+    }
+
+    let version = monitor.snapshot().report_version;
+    let html = match report {
+        Some(report) => render(&report),
+        None => message_page("Waiting for the first scenario to complete..."),
+    };
+    html.replacen("<body>", &format!("<body>\n{}", live_panel(version)), 1)
+}
+
+fn message_page(message: &str) -> String {
+    format!(r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="UTF-8">
+    <title>Load Test Report</title>
+    <style>
+        body {{ font-family: Arial, sans-serif; margin: 40px; line-height: 1.6; }}
+    </style>
+</head>
+<body>
+    <p>{}</p>
+</body>
+</html>"#, message)
+}
+
+fn live_panel(report_version: usize) -> String {
+    format!(r#"
+    <div id="live-panel" style="position: sticky; top: 0; z-index: 10; border: 1px solid #ddd; background-color: #fffbe6; padding: 10px 20px; margin-bottom: 20px;">
+        <strong>LIVE</strong> <span id="live-stage">connecting</span>
+        | Scenario <span id="live-scenario">-</span> (concurrency <span id="live-concurrency">-</span>)
+        | Endpoint <span id="live-endpoint">-</span><br>
+        Elapsed <span id="live-elapsed">-</span> | Remaining ~<span id="live-remaining">-</span>
+        | Requests <span id="live-completed">0</span>/<span id="live-planned">0</span>
+        | RPS <span id="live-rps">0</span> | In flight <span id="live-inflight">0</span>
+        | p50/p95/p99 <span id="live-latency">-</span>
+        | Errors <span id="live-errors">none</span>
+    </div>
+    <script>
+        (function() {{
+            const reportVersion = {};
+            const fmt = s => s == null ? '-' : new Date(s * 1000).toISOString().substr(11, 8);
+            const set = (id, value) => document.getElementById(id).textContent = value;
+            const events = new EventSource('/events');
+            events.onmessage = function(event) {{
+                const s = JSON.parse(event.data);
+                set('live-stage', s.stage);
+                set('live-scenario', s.scenario_index + '/' + s.scenario_count);
+                set('live-concurrency', s.concurrency);
+                set('live-endpoint', s.current_endpoint || '-');
+                set('live-elapsed', fmt(s.elapsed_seconds));
+                set('live-remaining', fmt(s.remaining_seconds));
+                set('live-completed', s.completed_requests);
+                set('live-planned', s.planned_requests);
+                set('live-rps', s.rolling_rps.toFixed(2));
+                set('live-inflight', s.in_flight);
+                set('live-latency', s.rolling_p50_latency + 'ms / ' + s.rolling_p95_latency + 'ms / ' + s.rolling_p99_latency + 'ms');
+                const errors = Object.entries(s.errors_by_class).map(([k, v]) => k + ': ' + v).join(', ');
+                set('live-errors', errors || 'none');
+                if (s.report_version !== reportVersion || s.stage === 'finished') {{
+                    events.close();
+                    location.reload();
+                }}
+            }};
+        }})();
+    </script>
+"#, report_version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load_tester::build_report;
+    use chrono::Local;
+    use std::fs;
+
+    fn custom_template(name: &str, source: &str) -> ReportOptions {
+        let path = std::env::temp_dir().join(format!("load-test-rs-{}-{}.html.j2", name, std::process::id()));
+        fs::write(&path, source).unwrap();
+        ReportOptions { html_template: Some(path), ..Default::default() }
+    }
+
+    #[test]
+    fn waits_for_the_first_scenario() {
+        let page = render_page(&LiveMonitor::new(), &ReportOptions::default());
+        assert!(page.contains("Waiting for the first scenario to complete..."));
+        assert!(page.contains("id=\"live-panel\""));
+    }
+
+    #[test]
+    fn renders_with_the_run_report_options() {
+        let options = custom_template("live", "<html><body>Custom report, {{ report.scenarios|length }} scenarios</body></html>");
+        let monitor = LiveMonitor::new();
+        monitor.publish_report(build_report("http://api.test", Local::now(), Duration::from_secs(1), Vec::new(), &[]));
+
+        let page = render_page(&monitor, &options);
+        assert!(page.contains("Custom report, 0 scenarios"));
+        assert!(page.contains("id=\"live-panel\""));
+
+        monitor.finish_run();
+        let page = render_page(&monitor, &options);
+        assert!(page.contains("Custom report, 0 scenarios"));
+        assert!(!page.contains("id=\"live-panel\""));
+        fs::remove_file(options.html_template.unwrap()).unwrap();
+    }
+
+    #[test]
+    fn shows_template_errors() {
+        let options = custom_template("live-broken", "<html><body>{{ report.base_url </body></html>");
+        let monitor = LiveMonitor::new();
+        monitor.publish_report(build_report("http://api.test", Local::now(), Duration::from_secs(1), Vec::new(), &[]));
+        let page = render_page(&monitor, &options);
+        assert!(page.contains("Error rendering the report"));
+        fs::remove_file(options.html_template.unwrap()).unwrap();
+    }
+}
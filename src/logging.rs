@@ -0,0 +1,53 @@
+use std::fs::File;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing_subscriber::EnvFilter;
+
+/// Line format for log output
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json, // One JSON object per event, for log shippers
+}
+
+/// Where log events go and which ones are kept.
+///
+/// The library only emits `tracing` events; nothing is printed unless a subscriber
+/// is installed, either by the embedding application or with [`init_logging`].
+#[derive(Clone, Debug)]
+pub struct LoggingConfig {
+    pub filter: String, // `EnvFilter` directives, e.g. `info` or `warn,load_test_rs::load_tester=debug`
+    pub format: LogFormat,
+    pub file: Option<PathBuf>, // Append to this file instead of writing to stderr
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            filter: "info".to_string(),
+            format: LogFormat::Text,
+            file: None,
+        }
+    }
+}
+
+/// Install a global `tracing` subscriber for `config`. `RUST_LOG`, when set, overrides `config.filter`.
+pub fn init_logging(config: &LoggingConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let filter = EnvFilter::try_from_default_env().or_else(|_| EnvFilter::try_new(&config.filter))?;
+    let builder = tracing_subscriber::fmt().with_env_filter(filter).with_target(true);
+
+    let installed = match (&config.file, config.format) {
+        (Some(path), LogFormat::Text) => {
+            let file = File::options().create(true).append(true).open(path)?;
+            builder.with_ansi(false).with_writer(Mutex::new(file)).try_init()
+        }
+        (Some(path), LogFormat::Json) => {
+            let file = File::options().create(true).append(true).open(path)?;
+            builder.json().with_writer(Mutex::new(file)).try_init()
+        }
+        (None, LogFormat::Text) => builder.with_writer(std::io::stderr).try_init(),
+        (None, LogFormat::Json) => builder.json().with_writer(std::io::stderr).try_init(),
+    };
+    installed.map_err(|e| e as Box<dyn std::error::Error>)
+}
@@ -1,11 +1,95 @@
-use load_test_rs::{LoadTestConfig, LoadTester, save_report};
+use load_test_rs::{
+    AddressSelection, ChartMode, ClientIdentity, ConnectionModel, EventStream, GraphqlOperation, GrpcCall,
+    GrpcDescriptors, HistoryStore, HttpVersion, LoadTestConfig, LoadTester, LogFormat, LoggingConfig, MetricsSinkConfig,
+    ReportOptions, RequestLogConfig, RunMetadata, StreamMode, Thresholds, TlsVersion, WebSocketScenario,
+    DEFAULT_CSV_DIR, DEFAULT_GRAPHQL_PATH, DEFAULT_HISTORY_DIR, DEFAULT_REQUEST_LOG, DEFAULT_REQUEST_RECORDS, init_logging,
+    parse_endpoint_client, parse_labels, parse_resolve, read_request_log, rebuild_report, run_dashboard,
+    save_history_report, save_report_with_options, serve_live_dashboard,
+};
+use std::collections::BTreeMap;
 use std::env;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+/// Value following `flag` on the command line, e.g. `--serve 127.0.0.1:9090`
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.windows(2)
+        .find(|pair| pair[0] == flag)
+        .map(|pair| pair[1].as_str())
+}
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+
+    // Progress from the library goes through `tracing`; the dashboard owns the terminal, so
+    // with `--tui` events are only written when a log file is given
+    let use_dashboard = args.iter().any(|arg| arg == "--tui");
+    let logging = LoggingConfig {
+        filter: flag_value(&args, "--log-level").unwrap_or("info").to_string(),
+        format: if flag_value(&args, "--log-format") == Some("json") { LogFormat::Json } else { LogFormat::Text },
+        file: flag_value(&args, "--log-file").map(PathBuf::from),
+    };
+    if (!use_dashboard || logging.file.is_some())
+        && let Err(e) = init_logging(&logging)
+    {
+        eprintln!("❌ Error setting up logging: {}", e);
+    }
+
+    if args.get(1).map(|s| s.as_str()) == Some("history") {
+        let history_dir = args.get(2).map(|s| s.as_str()).unwrap_or(DEFAULT_HISTORY_DIR);
+        match HistoryStore::open(history_dir).and_then(|store| save_history_report(&store, "history.html")) {
+            Ok(runs) => println!("✅ History report generated from {} runs: history.html", runs),
+            Err(e) => eprintln!("❌ Error generating history report: {}", e),
+        }
+        return;
+    }
+
+    // `run` is optional: `load-test-rs run quick` == `load-test-rs quick`
+    if args.get(1).map(|s| s.as_str()) == Some("run") {
+        args.remove(1);
+    }
+
+    // `--label key=value` tags the run in the history store (repeatable)
+    let mut labels = BTreeMap::new();
+    for pair in args.windows(2).filter(|pair| pair[0] == "--label") {
+        labels.extend(parse_labels(&pair[1]));
+    }
+    let record_history = !args.iter().any(|arg| arg == "--no-history");
+    let serve_addr = flag_value(&args, "--serve");
+    let report_options = ReportOptions {
+        chart_mode: if args.iter().any(|arg| arg == "--cdn-charts") { ChartMode::Cdn } else { ChartMode::Embedded },
+        html_template: flag_value(&args, "--template").map(PathBuf::from),
+        markdown_template: flag_value(&args, "--markdown-template").map(PathBuf::from),
+        thresholds: Thresholds {
+            min_success_rate: flag_value(&args, "--min-success-rate")
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(Thresholds::default().min_success_rate),
+            max_p95_latency: flag_value(&args, "--max-p95").and_then(|s| s.parse().ok()),
+            max_p99_latency: flag_value(&args, "--max-p99").and_then(|s| s.parse().ok()),
+            min_rps: flag_value(&args, "--min-rps").and_then(|s| s.parse().ok()),
+        },
+    };
+
+    // `report [requests.jsonl]` rebuilds the reports from a request log instead of running a test
+    if args.get(1).map(|s| s.as_str()) == Some("report") {
+        let log_path = args.get(2)
+            .filter(|arg| !arg.starts_with("--"))
+            .map(|s| s.as_str())
+            .unwrap_or(DEFAULT_REQUEST_LOG);
+        let rebuilt = read_request_log(Path::new(log_path)).and_then(|entries| {
+            println!("📥 Read {} requests from {}", entries.len(), log_path);
+            rebuild_report(&entries)
+        });
+        match rebuilt.and_then(|report| save_report_with_options(&report, &report_options)) {
+            Ok(_) => println!("✅ Reports rebuilt from {}", log_path),
+            Err(e) => eprintln!("❌ Error rebuilding reports from {}: {}", log_path, e),
+        }
+        return;
+    }
     
     // Create configuration based on command line arguments
-    let config = match args.get(1).map(|s| s.as_str()) {
+    let mut config = match args.get(1).map(|s| s.as_str()) {
         Some("quick") => {
             println!("🚀 Running Quick Scaling Test (4 scenarios: 50 -> 100 -> 200 -> 500)");
             LoadTestConfig::new_quick_scaling()
@@ -27,6 +111,75 @@ fn main() {
             println!("  cargo run quick           # Quick test (4 scenarios)");
             println!("  cargo run custom 5000     # Custom max concurrency");
             println!("  cargo run full            # Full scaling test (default)");
+            println!("  cargo run history [dir]   # Trend report from stored runs");
+            println!("  cargo run report [log]    # Rebuild reports from a request log (default requests.jsonl)");
+            println!("  --label env=staging       # Tag the run in the history store");
+            println!("  --no-history              # Don't record the run in the history store");
+            println!("  --tui                     # Live terminal dashboard (p: pause, s: skip, q: abort)");
+            println!("  --log-level info          # Log filter, e.g. warn,load_test_rs::load_tester=debug (RUST_LOG wins)");
+            println!("  --log-format json         # Log one JSON object per event instead of text");
+            println!("  --log-file FILE           # Append logs to a file instead of stderr");
+            println!("  --serve 127.0.0.1:9090    # Live web dashboard (and Prometheus /metrics) while the test runs");
+            println!("  --cdn-charts              # Load Chart.js from a CDN instead of embedding SVG charts");
+            println!("  --template FILE           # Render index.html from a custom template");
+            println!("  --markdown-template FILE  # Render README.md from a custom template");
+            println!("  --min-success-rate 99     # JUnit threshold: minimum success rate (%, default 95)");
+            println!("  --max-p95 500             # JUnit threshold: maximum P95 latency (ms)");
+            println!("  --max-p99 1000            # JUnit threshold: maximum P99 latency (ms)");
+            println!("  --min-rps 100             # JUnit threshold: minimum scenario RPS");
+            println!("  --record-requests         # Stream one row per request to requests.parquet (parquet feature) or requests.csv");
+            println!("  --request-log FILE        # Log requests as JSONL (e.g. requests.jsonl)");
+            println!("  --log-sample-rate 0.01    # Fraction of successes logged; errors are always logged");
+            println!("  --log-max-mb 100          # Rotate the request log past this size");
+            println!("  --log-slow-ms 1000        # Always log requests at least this slow");
+            println!("  --influx URL              # Push interval aggregates to an InfluxDB write URL (precision=ms)");
+            println!("  --influx-token TOKEN      # InfluxDB API token");
+            println!("  --statsd HOST:PORT       # Push interval aggregates to StatsD");
+            println!("  --dogstatsd HOST:PORT    # Push interval aggregates to DogStatsD, with tags");
+            println!("  --otlp URL                # Push interval aggregates to an OTLP/HTTP endpoint (/v1/metrics)");
+            println!("  --push-interval 10        # Seconds between metric pushes");
+            println!("  --run-id ID               # run_id tag on pushed metrics (default: start time)");
+            println!("  --no-trace-headers        # Don't send a W3C traceparent header with each request");
+            println!("  --request-id-header NAME  # Also send a random UUID in this header (e.g. X-Request-ID)");
+            println!("  --slowest 5               # Slowest requests per endpoint listed in the report");
+            println!("  --timeout 30              # Request timeout (seconds)");
+            println!("  --connect-timeout 10      # Connect timeout (seconds)");
+            println!("  --idle-timeout 30         # Close pooled connections idle this long (seconds)");
+            println!("  --pool-size 50            # Idle connections kept per host");
+            println!("  --max-connections N       # Cap connections (requests in flight) at once");
+            println!("  --no-keep-alive           # Send Connection: close and don't reuse connections");
+            println!("  --http-version 2          # auto (ALPN), 1.1 or 2 (prior knowledge)");
+            println!("  --connections per-user    # shared pool, per-user (one per virtual user) or per-request");
+            println!("  --ca-cert FILE            # Also trust the CA certificates in this PEM file (repeatable)");
+            println!("  --client-cert FILE        # Client certificate chain (PEM) for mutual TLS; needs --client-key");
+            println!("  --client-key FILE         # Private key (PEM) for --client-cert");
+            println!("  --insecure                # Don't verify server certificates (self-signed local targets)");
+            println!("  --tls-min 1.3             # Minimum TLS version: 1.2 (default) or 1.3");
+            println!("  --sni NAME                # Send NAME as SNI and Host while connecting to the base URL's host");
+            println!("  --resolve HOST=IP[,IP]    # Connect to these addresses instead of DNS (repeatable)");
+            println!("  --address-selection MODE  # Spread requests over --resolve/--source-ip addresses: round-robin (default) or random");
+            println!("  --proxy URL               # Send through an http://, https://, socks5:// or socks5h:// proxy");
+            println!("  --source-ip IP[,IP]       # Send from these local addresses, spread like --resolve addresses");
+            println!("  --endpoint-client P:K=V   # Client settings for one endpoint path, e.g. /upload:timeout=120,connections=per-request (repeatable)");
+            println!("  --cookie-jar              # Keep cookies per virtual user across a scenario's endpoints");
+            println!("  --cookie 'sid=abc'        # Seed every user's jar with a Set-Cookie style value (repeatable)");
+            println!("  --graphql NAME=FILE       # Also send operation NAME from the query document FILE (repeatable)");
+            println!("  --graphql-vars NAME=JSON  # Variables template for NAME, e.g. '{{\"id\": \"{{{{ user_id }}}}\"}}' (also request, user)");
+            println!("  --graphql-path /graphql   # Path GraphQL operations are posted to");
+            println!("  --grpc pkg.Service/Method # Also call a unary or server-streaming gRPC method (repeatable)");
+            println!("  --grpc-target URL         # gRPC server, e.g. http://127.0.0.1:50051 (default: the base URL)");
+            println!("  --grpc-message M=JSON     # Request template for method M, e.g. '{{\"id\": {{{{ request }}}}}}' (also user, user_id)");
+            println!("  --proto FILE              # .proto defining the gRPC methods (repeatable; default: server reflection)");
+            println!("  --proto-include DIR       # Import path for --proto files (repeatable; default: each file's directory)");
+            println!("  --ws /chat                # Also run a WebSocket endpoint in every scenario (repeatable)");
+            println!("  --ws-message TEXT         # Message template using {{{{ connection }}}}, {{{{ sequence }}}}, {{{{ user_id }}}} (repeatable, sent in turn)");
+            println!("  --ws-connections N       # WebSocket connections held open (default: the scenario's concurrency)");
+            println!("  --ws-messages 10          # Messages sent on each WebSocket connection");
+            println!("  --ws-rate 1               # Messages per second on each WebSocket connection");
+            println!("  --sse /notifications      # Also hold a Server-Sent Events stream open in every scenario (repeatable)");
+            println!("  --long-poll /updates      # Also long-poll an endpoint in every scenario (repeatable)");
+            println!("  --stream-subscribers N    # SSE/long-poll subscribers held together (default: the scenario's concurrency)");
+            println!("  --stream-hold 30          # Seconds each SSE/long-poll subscriber stays subscribed");
             println!();
             LoadTestConfig::new()
         }
@@ -47,19 +200,268 @@ fn main() {
     println!();
     
     // Create and run load tester
-    let load_tester = LoadTester::new(config);
-    let report = load_tester.run();
+    if let Some(path) = flag_value(&args, "--request-log") {
+        let defaults = RequestLogConfig::default();
+        config.request_log = Some(RequestLogConfig {
+            path: PathBuf::from(path),
+            success_sample_rate: flag_value(&args, "--log-sample-rate")
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(defaults.success_sample_rate),
+            max_file_bytes: flag_value(&args, "--log-max-mb")
+                .and_then(|s| s.parse::<u64>().ok())
+                .map(|mb| mb * 1024 * 1024)
+                .unwrap_or(defaults.max_file_bytes),
+            slow_request_ms: flag_value(&args, "--log-slow-ms").and_then(|s| s.parse().ok()),
+            ..defaults
+        });
+    }
+    if args.iter().any(|arg| arg == "--record-requests") {
+        config.record_requests = Some(PathBuf::from(DEFAULT_REQUEST_RECORDS));
+    }
+    if let Some(write_url) = flag_value(&args, "--influx") {
+        config.metrics_sinks.push(MetricsSinkConfig::Influx {
+            write_url: write_url.to_string(),
+            token: flag_value(&args, "--influx-token").map(String::from),
+        });
+    }
+    for (flag, dogstatsd) in [("--statsd", false), ("--dogstatsd", true)] {
+        if let Some(addr) = flag_value(&args, flag) {
+            config.metrics_sinks.push(MetricsSinkConfig::Statsd {
+                addr: addr.to_string(),
+                prefix: "load_test".to_string(),
+                dogstatsd,
+            });
+        }
+    }
+    if let Some(endpoint) = flag_value(&args, "--otlp") {
+        config.metrics_sinks.push(MetricsSinkConfig::Otlp { endpoint: endpoint.to_string() });
+    }
+    if let Some(seconds) = flag_value(&args, "--push-interval").and_then(|s| s.parse::<f64>().ok())
+        && seconds > 0.0
+    {
+        config.metrics_push_interval = Duration::from_secs_f64(seconds);
+    }
+    config.run_id = flag_value(&args, "--run-id").map(String::from);
+    config.trace_headers = !args.iter().any(|arg| arg == "--no-trace-headers");
+    config.request_id_header = flag_value(&args, "--request-id-header").map(String::from);
+    if let Some(count) = flag_value(&args, "--slowest").and_then(|s| s.parse().ok()) {
+        config.slowest_requests = count;
+    }
+    let seconds = |flag| flag_value(&args, flag)
+        .and_then(|s| s.parse::<f64>().ok())
+        .filter(|seconds| *seconds > 0.0)
+        .map(Duration::from_secs_f64);
+    let client = &mut config.http_client;
+    client.request_timeout = seconds("--timeout").unwrap_or(client.request_timeout);
+    client.connect_timeout = seconds("--connect-timeout").unwrap_or(client.connect_timeout);
+    client.pool_idle_timeout = seconds("--idle-timeout").or(client.pool_idle_timeout);
+    if let Some(size) = flag_value(&args, "--pool-size").and_then(|s| s.parse().ok()) {
+        client.pool_max_idle_per_host = size;
+    }
+    client.max_connections = flag_value(&args, "--max-connections").and_then(|s| s.parse().ok());
+    client.keep_alive = !args.iter().any(|arg| arg == "--no-keep-alive");
+    if let Some(model) = flag_value(&args, "--connections") {
+        match ConnectionModel::parse(model) {
+            Some(model) => client.connection_model = model,
+            None => eprintln!("⚠️  Unknown --connections {}; expected shared, per-user or per-request", model),
+        }
+    }
+    if let Some(version) = flag_value(&args, "--http-version") {
+        match HttpVersion::parse(version) {
+            Some(version) => client.http_version = version,
+            None => eprintln!("⚠️  Unknown --http-version {}; expected auto, 1.1 or 2", version),
+        }
+    }
+    client.tls.ca_certificates = args.windows(2)
+        .filter(|pair| pair[0] == "--ca-cert")
+        .map(|pair| PathBuf::from(&pair[1]))
+        .collect();
+    match (flag_value(&args, "--client-cert"), flag_value(&args, "--client-key")) {
+        (Some(certificate), Some(key)) => {
+            client.tls.client_identity = Some(ClientIdentity {
+                certificate: PathBuf::from(certificate),
+                key: PathBuf::from(key),
+            });
+        }
+        (None, None) => {}
+        _ => eprintln!("⚠️  --client-cert and --client-key must be given together; not using a client certificate"),
+    }
+    client.tls.insecure = args.iter().any(|arg| arg == "--insecure");
+    if let Some(version) = flag_value(&args, "--tls-min") {
+        match TlsVersion::parse(version) {
+            Some(version) => client.tls.min_version = version,
+            None => eprintln!("⚠️  Unknown --tls-min {}; expected 1.2 or 1.3", version),
+        }
+    }
+    client.tls.server_name = flag_value(&args, "--sni").map(String::from);
+    for pair in args.windows(2).filter(|pair| pair[0] == "--resolve") {
+        match parse_resolve(&pair[1]) {
+            Some((host, addresses)) => {
+                client.resolve.insert(host, addresses);
+            }
+            None => eprintln!("⚠️  Invalid --resolve {}; expected HOST=IP[,IP...]", pair[1]),
+        }
+    }
+    client.proxy = flag_value(&args, "--proxy").map(String::from);
+    if let Some(sources) = flag_value(&args, "--source-ip") {
+        for source in sources.split(',') {
+            match source.trim().parse() {
+                Ok(source) => client.source_addresses.push(source),
+                Err(_) => eprintln!("⚠️  Invalid --source-ip address {}", source),
+            }
+        }
+    }
+    if let Some(selection) = flag_value(&args, "--address-selection") {
+        match AddressSelection::parse(selection) {
+            Some(selection) => client.address_selection = selection,
+            None => eprintln!("⚠️  Unknown --address-selection {}; expected round-robin or random", selection),
+        }
+    }
+    // Per-endpoint overrides start from the global client settings above
+    for pair in args.windows(2).filter(|pair| pair[0] == "--endpoint-client") {
+        match parse_endpoint_client(&pair[1], &config.http_client) {
+            Ok((path, client)) => {
+                config.endpoint_http_clients.insert(path, client);
+            }
+            Err(e) => eprintln!("⚠️  Invalid --endpoint-client {}: {}", pair[1], e),
+        }
+    }
+    config.seed_cookies = args.windows(2)
+        .filter(|pair| pair[0] == "--cookie")
+        .map(|pair| pair[1].clone())
+        .collect();
+    config.cookie_jar = !config.seed_cookies.is_empty() || args.iter().any(|arg| arg == "--cookie-jar");
+    let graphql_path = flag_value(&args, "--graphql-path").unwrap_or(DEFAULT_GRAPHQL_PATH);
+    for pair in args.windows(2).filter(|pair| pair[0] == "--graphql") {
+        let Some((name, file)) = pair[1].split_once('=') else {
+            eprintln!("⚠️  Invalid --graphql {}; expected NAME=FILE", pair[1]);
+            continue;
+        };
+        match std::fs::read_to_string(file) {
+            Ok(query) => {
+                let mut operation = GraphqlOperation::new(name, &query);
+                operation.path = graphql_path.to_string();
+                operation.variables = args.windows(2)
+                    .filter(|pair| pair[0] == "--graphql-vars")
+                    .find_map(|pair| pair[1].strip_prefix(name).and_then(|rest| rest.strip_prefix('=')))
+                    .map(String::from);
+                config.graphql_operations.push(operation);
+            }
+            Err(e) => eprintln!("⚠️  Error reading GraphQL query {}: {}", file, e),
+        }
+    }
+    let descriptors = {
+        let files: Vec<PathBuf> = args.windows(2)
+            .filter(|pair| pair[0] == "--proto")
+            .map(|pair| PathBuf::from(&pair[1]))
+            .collect();
+        if files.is_empty() {
+            GrpcDescriptors::Reflection
+        } else {
+            let include_paths = args.windows(2)
+                .filter(|pair| pair[0] == "--proto-include")
+                .map(|pair| PathBuf::from(&pair[1]))
+                .collect();
+            GrpcDescriptors::ProtoFiles { files, include_paths }
+        }
+    };
+    let grpc_target = flag_value(&args, "--grpc-target").unwrap_or(&config.base_url).to_string();
+    for pair in args.windows(2).filter(|pair| pair[0] == "--grpc") {
+        let method = pair[1].as_str();
+        let mut call = GrpcCall::new(&grpc_target, method, descriptors.clone());
+        if let Some(message) = args.windows(2)
+            .filter(|pair| pair[0] == "--grpc-message")
+            .find_map(|pair| pair[1].strip_prefix(method).and_then(|rest| rest.strip_prefix('=')))
+        {
+            call.message = message.to_string();
+        }
+        config.grpc_calls.push(call);
+    }
+    let ws_messages: Vec<String> = args.windows(2)
+        .filter(|pair| pair[0] == "--ws-message")
+        .map(|pair| pair[1].clone())
+        .collect();
+    for pair in args.windows(2).filter(|pair| pair[0] == "--ws") {
+        let mut websocket = WebSocketScenario::new(&pair[1]);
+        if !ws_messages.is_empty() {
+            websocket.messages = ws_messages.clone();
+        }
+        websocket.connections = flag_value(&args, "--ws-connections").and_then(|s| s.parse().ok());
+        if let Some(count) = flag_value(&args, "--ws-messages").and_then(|s| s.parse().ok()) {
+            websocket.messages_per_connection = count;
+        }
+        if let Some(rate) = flag_value(&args, "--ws-rate").and_then(|s| s.parse().ok()) {
+            websocket.message_rate = rate;
+        }
+        config.websockets.push(websocket);
+    }
+    let streams = args.windows(2).filter_map(|pair| match pair[0].as_str() {
+        "--sse" => Some(EventStream::new(&pair[1], StreamMode::ServerSentEvents)),
+        "--long-poll" => Some(EventStream::new(&pair[1], StreamMode::LongPoll)),
+        _ => None,
+    });
+    for mut stream in streams {
+        stream.subscribers = flag_value(&args, "--stream-subscribers").and_then(|s| s.parse().ok());
+        stream.hold = seconds("--stream-hold").unwrap_or(stream.hold);
+        config.event_streams.push(stream);
+    }
+    let load_tester = match LoadTester::new(config) {
+        Ok(load_tester) => load_tester,
+        Err(e) => {
+            eprintln!("❌ Error setting up the load test: {}", e);
+            return;
+        }
+    };
+    let monitor = load_tester.monitor();
+
+    let server = serve_addr.and_then(|addr| match serve_live_dashboard(addr, monitor.clone(), report_options.clone()) {
+        Ok(handle) => {
+            println!("🌐 Live dashboard: http://{}/ (Prometheus metrics: /metrics)", addr);
+            Some(handle)
+        },
+        Err(e) => {
+            eprintln!("❌ Error starting live dashboard on {}: {}", addr, e);
+            None
+        }
+    });
+
+    let report = if use_dashboard {
+        let handle = thread::spawn(move || load_tester.run());
+        if let Err(e) = run_dashboard(&monitor, || handle.is_finished()) {
+            eprintln!("❌ Dashboard error: {}", e);
+        }
+        handle.join().expect("Load test thread panicked")
+    } else {
+        load_tester.run()
+    };
 
     // Save the reports
-    match save_report(&report) {
+    match save_report_with_options(&report, &report_options) {
         Ok(_) => {
             println!("✅ Reports generated successfully!");
             println!("📄 HTML Report: index.html");
             println!("📄 Markdown Report: README.md");
             println!("📄 JSON Data: load_test_data.json");
+            println!("📄 JUnit XML: junit.xml");
+            println!("📄 CSV Tables: {}/", DEFAULT_CSV_DIR);
         },
         Err(e) => {
             eprintln!("❌ Error generating reports: {}", e);
         }
     }
+
+    if record_history {
+        match HistoryStore::open(DEFAULT_HISTORY_DIR)
+            .and_then(|store| store.append(&report, RunMetadata::detect(labels)))
+        {
+            Ok(entry) => println!("🗄️  Run {} recorded in {}/", entry.run_id, DEFAULT_HISTORY_DIR),
+            Err(e) => eprintln!("❌ Error recording run history: {}", e),
+        }
+    }
+
+    // Keep serving the final (frozen) report until interrupted
+    if let Some(server) = server {
+        println!("🌐 Serving final report at http://{}/ (Ctrl-C to exit)", serve_addr.unwrap_or_default());
+        let _ = server.join();
+    }
 }
@@ -1,4 +1,11 @@
-#[derive(Clone)]
+use crate::live::LATENCY_BUCKETS_MS;
+use crate::models::TimeSeriesBucket;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Default)]
 pub struct LatencyMetrics {
     pub latencies: Vec<u64>, // in milliseconds
 }
@@ -23,15 +30,43 @@ pub enum DistributionType {
     Unknown,
 }
 
-#[derive(Debug, Clone)]
+/// A percentile estimate together with confidence intervals for that percentile
+/// (not for the mean), so a P99 from a few hundred samples can be judged.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PercentileWithConfidence {
+    pub percentile: f64,
     pub value: u64,
-    pub confidence_interval_lower: f64,
-    pub confidence_interval_upper: f64,
-    pub alpha: f64, // Significance level (0.05 for 95%, 0.01 for 99%)
-    pub z_score: f64, // Standard score
+    pub confidence_level: f64, // 0.95 for a 95% interval
+    pub sample_size: usize,
+    // Distribution-free interval from order statistics (binomial ranks)
+    pub order_statistic_lower: u64,
+    pub order_statistic_upper: u64,
+    // Percentile bootstrap interval
+    pub bootstrap_lower: u64,
+    pub bootstrap_upper: u64,
+}
+
+impl PercentileWithConfidence {
+    /// Width of the order-statistic interval relative to the estimate
+    pub fn relative_width(&self) -> f64 {
+        if self.value == 0 {
+            return 0.0;
+        }
+        (self.order_statistic_upper - self.order_statistic_lower) as f64 / self.value as f64
+    }
 }
 
+const DEFAULT_CONFIDENCE_LEVEL: f64 = 0.95;
+const DEFAULT_BOOTSTRAP_RESAMPLES: usize = 1000;
+/// Largest sample the bootstrap resamples; bigger runs are thinned to this size
+const MAX_BOOTSTRAP_SAMPLES: usize = 20_000;
+const BOOTSTRAP_SEED: u64 = 0x5eed_1a7e_4c1e;
+
+/// Percentiles in the HDR-style spectrum plots, p50 through p99.999
+pub const PERCENTILE_SPECTRUM: [f64; 8] = [50.0, 75.0, 90.0, 95.0, 99.0, 99.9, 99.99, 99.999];
+/// Bins in the per-endpoint latency histograms
+pub const HISTOGRAM_BINS: usize = 30;
+
 impl LatencyMetrics {
     pub fn new() -> Self {
         LatencyMetrics {
@@ -43,6 +78,11 @@ impl LatencyMetrics {
         self.latencies.push(latency_ms);
     }
 
+    /// Add `latency_ms` `count` times, e.g. for a sampled log entry standing for `count` requests
+    pub fn add_latency_times(&mut self, latency_ms: u64, count: usize) {
+        self.latencies.extend(std::iter::repeat_n(latency_ms, count));
+    }
+
     pub fn mean(&self) -> f64 {
         if self.latencies.is_empty() {
             return 0.0;
@@ -62,25 +102,98 @@ impl LatencyMetrics {
         sorted[index.min(sorted.len() - 1)]
     }
 
-    /// Advanced percentile with statistical confidence and alpha values
+    /// Percentile with 95% order-statistic and bootstrap confidence intervals
     pub fn percentile_with_confidence(&self, percentile: f64) -> PercentileWithConfidence {
-        let alpha = (100.0 - percentile) / 100.0; // 0.05 for P95, 0.01 for P99
-        let z_score = self.calculate_z_score(percentile);
-        
-        let value = self.percentile(percentile);
-        let std_dev = self.standard_deviation();
-        let mean = self.mean();
-        
-        // Calculate confidence interval using normal distribution approximation
-        let margin_of_error = z_score * (std_dev / (self.latencies.len() as f64).sqrt());
-        
+        let (order_statistic_lower, order_statistic_upper) =
+            self.percentile_order_statistic_interval(percentile, DEFAULT_CONFIDENCE_LEVEL);
+        let (bootstrap_lower, bootstrap_upper) = self.percentile_bootstrap_interval(
+            percentile,
+            DEFAULT_CONFIDENCE_LEVEL,
+            DEFAULT_BOOTSTRAP_RESAMPLES,
+        );
+
         PercentileWithConfidence {
-            value,
-            confidence_interval_lower: mean - margin_of_error,
-            confidence_interval_upper: mean + margin_of_error,
-            alpha,
-            z_score,
+            percentile,
+            value: self.percentile(percentile),
+            confidence_level: DEFAULT_CONFIDENCE_LEVEL,
+            sample_size: self.latencies.len(),
+            order_statistic_lower,
+            order_statistic_upper,
+            bootstrap_lower,
+            bootstrap_upper,
+        }
+    }
+
+    /// Distribution-free confidence interval for a percentile.
+    ///
+    /// The number of samples at or below the true percentile follows
+    /// Binomial(n, p), so the interval is bounded by the order statistics whose
+    /// ranks cut off `(1 - confidence) / 2` of that distribution on each side.
+    /// With too few samples the bounds fall back to the sample min/max.
+    pub fn percentile_order_statistic_interval(&self, percentile: f64, confidence: f64) -> (u64, u64) {
+        if self.latencies.is_empty() {
+            return (0, 0);
+        }
+        let mut sorted = self.latencies.clone();
+        sorted.sort_unstable();
+        let n = sorted.len();
+        let p = (percentile / 100.0).clamp(0.0, 1.0);
+        let tail = (1.0 - confidence) / 2.0;
+        let cdf = binomial_cdf(n, p);
+
+        // Lower rank l (1-based): largest l with P(B <= l - 1) <= tail
+        let lower_rank = (1..=n)
+            .rev()
+            .find(|&l| cdf[l - 1] <= tail)
+            .unwrap_or(1);
+        // Upper rank u (1-based): smallest u with P(B >= u) <= tail
+        let upper_rank = (1..=n)
+            .find(|&u| 1.0 - cdf[u - 1] <= tail)
+            .unwrap_or(n);
+
+        (sorted[lower_rank - 1], sorted[upper_rank.max(lower_rank) - 1])
+    }
+
+    /// Percentile bootstrap confidence interval for a percentile.
+    ///
+    /// Uses a fixed seed so repeated report generation gives the same bounds.
+    /// Runs with more than `MAX_BOOTSTRAP_SAMPLES` latencies are resampled from
+    /// evenly spaced order statistics, which keeps the cost bounded at the
+    /// price of a somewhat wider (conservative) interval.
+    pub fn percentile_bootstrap_interval(&self, percentile: f64, confidence: f64, resamples: usize) -> (u64, u64) {
+        if self.latencies.is_empty() || resamples == 0 {
+            return (0, 0);
+        }
+        let thinned;
+        let samples = if self.latencies.len() > MAX_BOOTSTRAP_SAMPLES {
+            let mut sorted = self.latencies.clone();
+            sorted.sort_unstable();
+            thinned = (0..MAX_BOOTSTRAP_SAMPLES)
+                .map(|i| sorted[i * sorted.len() / MAX_BOOTSTRAP_SAMPLES])
+                .collect::<Vec<_>>();
+            &thinned
+        } else {
+            &self.latencies
+        };
+        let n = samples.len();
+        let index = ((percentile / 100.0).clamp(0.0, 1.0) * (n - 1) as f64).round() as usize;
+        let mut rng = StdRng::seed_from_u64(BOOTSTRAP_SEED);
+        let mut resample = vec![0u64; n];
+        let mut estimates = Vec::with_capacity(resamples);
+
+        for _ in 0..resamples {
+            for slot in resample.iter_mut() {
+                *slot = samples[rng.gen_range(0..n)];
+            }
+            let (_, value, _) = resample.select_nth_unstable(index);
+            estimates.push(*value);
         }
+
+        estimates.sort_unstable();
+        let tail = (1.0 - confidence) / 2.0;
+        let lower = ((tail * (resamples - 1) as f64).round() as usize).min(resamples - 1);
+        let upper = (((1.0 - tail) * (resamples - 1) as f64).round() as usize).min(resamples - 1);
+        (estimates[lower], estimates[upper])
     }
 
     /// Full statistical analysis including bell curve characteristics
@@ -128,7 +241,7 @@ impl LatencyMetrics {
         let mut sorted = self.latencies.clone();
         sorted.sort_unstable();
         let len = sorted.len();
-        if len % 2 == 0 {
+        if len.is_multiple_of(2) {
             (sorted[len / 2 - 1] + sorted[len / 2]) as f64 / 2.0
         } else {
             sorted[len / 2] as f64
@@ -214,29 +327,6 @@ impl LatencyMetrics {
         numerator * sum_fourth_z_scores - correction
     }
 
-    /// Calculate Z-score for given percentile (critical values for normal distribution)
-    fn calculate_z_score(&self, percentile: f64) -> f64 {
-        match percentile as u8 {
-            90 => 1.282,  // P90
-            95 => 1.645,  // P95
-            99 => 2.326,  // P99
-            _ => {
-                // Approximate calculation for other percentiles
-                let p = percentile / 100.0;
-                // Box-Muller transformation approximation
-                if p > 0.5 {
-                    let t = (-2.0 * (1.0 - p).ln()).sqrt();
-                    t - (2.515517 + 0.802853 * t + 0.010328 * t * t) / 
-                        (1.0 + 1.432788 * t + 0.189269 * t * t + 0.001308 * t * t * t)
-                } else {
-                    let t = (-2.0 * p.ln()).sqrt();
-                    -1.0 * (t - (2.515517 + 0.802853 * t + 0.010328 * t * t) / 
-                            (1.0 + 1.432788 * t + 0.189269 * t * t + 0.001308 * t * t * t))
-                }
-            }
-        }
-    }
-
     /// Determine if data follows normal distribution (bell curve)
     fn determine_distribution_type(&self, mean: f64, median: f64, skewness: f64, kurtosis: f64) -> DistributionType {
         let mean_median_ratio = (mean - median).abs() / mean.max(1.0);
@@ -283,16 +373,16 @@ impl LatencyMetrics {
 
         let min = *self.latencies.iter().min().unwrap();
         let max = *self.latencies.iter().max().unwrap();
-        let bin_size = if max > min { (max - min) / bins as u64 } else { 1 };
+        // Round up so the top bin ends at or past `max`, and never drop below 1ms
+        let bin_size = (max - min).div_ceil(bins.max(1) as u64).max(1);
 
         let mut histogram = vec![0; bins];
         
         for &latency in &self.latencies {
-            let bin_index = if bin_size > 0 {
-                ((latency - min) / bin_size).min(bins as u64 - 1) as usize
-            } else {
-                0
-            };
+            let bin_index = (latency - min)
+                .checked_div(bin_size)
+                .map(|bin| bin.min(bins as u64 - 1) as usize)
+                .unwrap_or(0);
             histogram[bin_index] += 1;
         }
 
@@ -301,4 +391,267 @@ impl LatencyMetrics {
             .map(|(i, count)| (min + i as u64 * bin_size, count))
             .collect()
     }
+
+    /// Latency at each of `PERCENTILE_SPECTRUM`, as (percentile, latency) pairs
+    pub fn percentile_spectrum(&self) -> Vec<(f64, u64)> {
+        if self.latencies.is_empty() {
+            return Vec::new();
+        }
+        let mut sorted = self.latencies.clone();
+        sorted.sort_unstable();
+        PERCENTILE_SPECTRUM.iter()
+            .map(|&percentile| {
+                let index = ((percentile / 100.0) * (sorted.len() - 1) as f64).round() as usize;
+                (percentile, sorted[index.min(sorted.len() - 1)])
+            })
+            .collect()
+    }
+
+    /// Requests per `LATENCY_BUCKETS_MS` bound (non-cumulative), plus a final overflow count
+    pub fn bucket_counts(&self) -> Vec<usize> {
+        let mut counts = vec![0; LATENCY_BUCKETS_MS.len() + 1];
+        for &latency in &self.latencies {
+            counts[LATENCY_BUCKETS_MS.partition_point(|bound| *bound < latency)] += 1;
+        }
+        counts
+    }
+}
+
+#[derive(Clone, Default)]
+struct BucketAccumulator {
+    requests: usize,
+    errors: usize,
+    active_connections: usize,
+    latencies: LatencyMetrics,
+}
+
+/// Buckets request results into fixed intervals from the scenario start
+pub struct TimeSeriesRecorder {
+    start: Instant,
+    interval: Duration,
+    buckets: Vec<BucketAccumulator>,
+}
+
+impl TimeSeriesRecorder {
+    pub fn new(interval: Duration) -> Self {
+        TimeSeriesRecorder {
+            start: Instant::now(),
+            interval: interval.max(Duration::from_millis(1)),
+            buckets: Vec::new(),
+        }
+    }
+
+    /// Record a request that completed at `completed_at` while `active`
+    /// requests (including this one) were in flight
+    pub fn record(&mut self, completed_at: Instant, latency_ms: u64, is_error: bool, active: usize) {
+        self.record_at_offset(completed_at.saturating_duration_since(self.start), latency_ms, is_error, active, 1);
+    }
+
+    /// Record a request that completed `elapsed` after the scenario start, e.g. from a request log.
+    /// A sampled log entry stands for `weight` requests, in the counts and the latencies.
+    pub fn record_at_offset(&mut self, elapsed: Duration, latency_ms: u64, is_error: bool, active: usize, weight: usize) {
+        let bucket = self.bucket_at(elapsed, active);
+        bucket.requests += weight;
+        if is_error {
+            bucket.errors += weight;
+        }
+        bucket.latencies.add_latency_times(latency_ms, weight);
+    }
+
+    /// Record a request that failed at `completed_at` before anything was sent: an error without a latency
+    pub fn record_unsent(&mut self, completed_at: Instant, active: usize) {
+        let bucket = self.bucket_at(completed_at.saturating_duration_since(self.start), active);
+        bucket.requests += 1;
+        bucket.errors += 1;
+    }
+
+    fn bucket_at(&mut self, elapsed: Duration, active: usize) -> &mut BucketAccumulator {
+        let index = (elapsed.as_secs_f64() / self.interval.as_secs_f64()) as usize;
+        if self.buckets.len() <= index {
+            self.buckets.resize(index + 1, BucketAccumulator::default());
+        }
+        let bucket = &mut self.buckets[index];
+        bucket.active_connections = bucket.active_connections.max(active);
+        bucket
+    }
+
+    pub fn buckets(&self) -> Vec<TimeSeriesBucket> {
+        let interval_secs = self.interval.as_secs_f64();
+        self.buckets.iter()
+            .enumerate()
+            .map(|(i, bucket)| TimeSeriesBucket {
+                offset_seconds: i as f64 * interval_secs,
+                requests: bucket.requests,
+                errors: bucket.errors,
+                rps: bucket.requests as f64 / interval_secs,
+                active_connections: bucket.active_connections,
+                mean_latency: bucket.latencies.mean(),
+                p50_latency: bucket.latencies.percentile(50.0),
+                p95_latency: bucket.latencies.percentile(95.0),
+                p99_latency: bucket.latencies.percentile(99.0),
+                latency_buckets: bucket.latencies.bucket_counts(),
+            })
+            .collect()
+    }
+}
+
+/// Cumulative distribution P(B <= k) for B ~ Binomial(n, p), k = 0..=n.
+///
+/// Computed in log space so large sample counts don't underflow.
+fn binomial_cdf(n: usize, p: f64) -> Vec<f64> {
+    let mut cdf = Vec::with_capacity(n + 1);
+    if p <= 0.0 {
+        cdf.resize(n + 1, 1.0);
+        return cdf;
+    }
+    if p >= 1.0 {
+        cdf.resize(n, 0.0);
+        cdf.push(1.0);
+        return cdf;
+    }
+
+    let log_ratio = (p / (1.0 - p)).ln();
+    let mut log_pmf = n as f64 * (1.0 - p).ln();
+    let mut total = 0.0;
+    for k in 0..=n {
+        total += log_pmf.exp();
+        cdf.push(total.min(1.0));
+        if k < n {
+            log_pmf += ((n - k) as f64 / (k + 1) as f64).ln() + log_ratio;
+        }
+    }
+    cdf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(latencies: impl IntoIterator<Item = u64>) -> LatencyMetrics {
+        LatencyMetrics { latencies: latencies.into_iter().collect() }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn binomial_cdf_matches_reference_values() {
+        let cdf = binomial_cdf(10, 0.5);
+        assert_eq!(cdf.len(), 11);
+        assert_close(cdf[0], 0.0009765625);
+        assert_close(cdf[5], 0.623046875);
+        assert_close(cdf[10], 1.0);
+
+        let cdf = binomial_cdf(20, 0.95);
+        assert_close(cdf[17], 0.07548367378849649);
+        assert_close(cdf[18], 0.2641604750561505);
+    }
+
+    #[test]
+    fn binomial_cdf_handles_degenerate_probabilities() {
+        assert_eq!(binomial_cdf(3, 0.0), vec![1.0; 4]);
+        assert_eq!(binomial_cdf(3, 1.0), vec![0.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn binomial_cdf_stays_finite_for_large_samples() {
+        let cdf = binomial_cdf(100_000, 0.99);
+        assert!(cdf.iter().all(|p| p.is_finite()));
+        assert_close(*cdf.last().unwrap(), 1.0);
+        assert!(cdf[99_000] > 0.4 && cdf[99_000] < 0.6);
+    }
+
+    #[test]
+    fn order_statistic_interval_uses_binomial_ranks() {
+        let latencies = metrics(1..=100);
+        // Classic 95% ranks for the median of 100 samples are 40 and 61
+        assert_eq!(latencies.percentile_order_statistic_interval(50.0, 0.95), (40, 61));
+        // Too few samples above P95 for an upper bound below the maximum
+        assert_eq!(latencies.percentile_order_statistic_interval(95.0, 0.95), (90, 100));
+    }
+
+    #[test]
+    fn order_statistic_interval_widens_to_min_max_for_small_samples() {
+        for n in 1..10 {
+            let latencies = metrics((1..=n).map(|v| v * 10));
+            let max = n * 10;
+            if n < 9 {
+                assert_eq!(latencies.percentile_order_statistic_interval(50.0, 0.95), (10, max), "n = {n}");
+            }
+            assert_eq!(latencies.percentile_order_statistic_interval(99.0, 0.95).1, max, "n = {n}");
+        }
+        // Nine samples are the first where the median interval drops the extremes
+        assert_eq!(metrics((1..=9).map(|v| v * 10)).percentile_order_statistic_interval(50.0, 0.95), (20, 80));
+        assert_eq!(metrics([]).percentile_order_statistic_interval(50.0, 0.95), (0, 0));
+    }
+
+    #[test]
+    fn bootstrap_interval_is_deterministic() {
+        let latencies = metrics((0..500).map(|i| (i * 37 % 101) as u64 + 5));
+        let first = latencies.percentile_bootstrap_interval(95.0, 0.95, 1000);
+        assert_eq!(first, latencies.percentile_bootstrap_interval(95.0, 0.95, 1000));
+
+        let p95 = latencies.percentile(95.0);
+        assert!(first.0 <= p95 && p95 <= first.1, "{first:?} does not contain {p95}");
+        assert_eq!(metrics([7; 50]).percentile_bootstrap_interval(99.0, 0.95, 200), (7, 7));
+        assert_eq!(metrics([]).percentile_bootstrap_interval(50.0, 0.95, 1000), (0, 0));
+    }
+
+    #[test]
+    fn bootstrap_interval_thins_large_samples() {
+        let latencies = metrics((0..(MAX_BOOTSTRAP_SAMPLES as u64 * 5)).rev());
+        let (lower, upper) = latencies.percentile_bootstrap_interval(50.0, 0.95, 100);
+        let median = latencies.percentile(50.0);
+        assert!(lower <= median && median <= upper);
+        assert!(upper - lower < median / 10);
+    }
+
+    #[test]
+    fn percentile_with_confidence_reports_sample_size() {
+        let result = metrics(1..=100).percentile_with_confidence(50.0);
+        assert_eq!(result.sample_size, 100);
+        assert_eq!((result.order_statistic_lower, result.order_statistic_upper), (40, 61));
+        assert!(result.bootstrap_lower <= result.value && result.value <= result.bootstrap_upper);
+    }
+
+    #[test]
+    fn time_series_buckets_by_offset_from_the_start() {
+        let mut recorder = TimeSeriesRecorder::new(Duration::from_secs(2));
+        recorder.record_at_offset(Duration::from_millis(100), 10, false, 3, 1);
+        recorder.record_at_offset(Duration::from_millis(1999), 30, true, 5, 1);
+        // An empty interval in between still gets a bucket
+        recorder.record_at_offset(Duration::from_millis(4000), 7, false, 1, 1);
+
+        let buckets = recorder.buckets();
+        assert_eq!(buckets.len(), 3);
+        assert_eq!(buckets.iter().map(|b| b.offset_seconds).collect::<Vec<_>>(), vec![0.0, 2.0, 4.0]);
+        assert_eq!((buckets[0].requests, buckets[0].errors, buckets[0].active_connections), (2, 1, 5));
+        assert_close(buckets[0].rps, 1.0);
+        assert_close(buckets[0].mean_latency, 20.0);
+        assert_eq!(buckets[0].p99_latency, 30);
+        assert_eq!(buckets[0].latency_buckets.iter().sum::<usize>(), 2);
+        assert_eq!((buckets[1].requests, buckets[1].p50_latency), (0, 0));
+        assert_eq!(buckets[2].p50_latency, 7);
+    }
+
+    #[test]
+    fn time_series_weights_counts_and_latencies() {
+        let mut recorder = TimeSeriesRecorder::new(Duration::from_secs(1));
+        recorder.record_at_offset(Duration::ZERO, 100, true, 1, 10);
+        recorder.record_at_offset(Duration::ZERO, 20, false, 1, 1);
+        recorder.record_unsent(recorder.start, 2);
+
+        let bucket = &recorder.buckets()[0];
+        assert_eq!((bucket.requests, bucket.errors, bucket.active_connections), (12, 11, 2));
+        assert_close(bucket.mean_latency, 1020.0 / 11.0);
+        assert_eq!(bucket.latency_buckets.iter().sum::<usize>(), 11);
+    }
+
+    #[test]
+    fn time_series_interval_has_a_floor() {
+        let mut recorder = TimeSeriesRecorder::new(Duration::ZERO);
+        recorder.record_at_offset(Duration::from_millis(3), 1, false, 1, 1);
+        assert_eq!(recorder.buckets().len(), 4);
+    }
 }
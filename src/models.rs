@@ -1,6 +1,44 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::net::IpAddr;
 use chrono::{DateTime, Local};
+use crate::http_client::HttpClientConfig;
+use crate::metrics::PercentileWithConfidence;
+
+/// Coarse failure category used for live counters and error breakdowns
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorClass {
+    ClientError, // HTTP 4xx
+    ServerError, // HTTP 5xx
+    Timeout,
+    Connect,
+    Graphql, // 2xx response whose GraphQL `errors` array is non-empty, or whose body isn't JSON
+    Other,
+}
+
+impl ErrorClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorClass::ClientError => "client_error",
+            ErrorClass::ServerError => "server_error",
+            ErrorClass::Timeout => "timeout",
+            ErrorClass::Connect => "connect",
+            ErrorClass::Graphql => "graphql",
+            ErrorClass::Other => "other",
+        }
+    }
+
+    /// Class for a received HTTP status, `None` for success
+    pub fn from_status(status: u16) -> Option<Self> {
+        match status {
+            200..=299 => None,
+            400..=499 => Some(ErrorClass::ClientError),
+            500..=599 => Some(ErrorClass::ServerError),
+            _ => Some(ErrorClass::Other),
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct Scenario {
@@ -15,10 +53,118 @@ pub struct EndpointResult {
     pub successful_requests: usize,
     pub failed_requests: usize,
     pub success_rate: f64,
+    #[serde(default)]
+    pub rps: f64,
+    pub mean_latency: f64,
+    pub p95_latency: u64,
+    pub p99_latency: u64,
+    #[serde(default)]
+    pub p95_confidence: PercentileWithConfidence,
+    #[serde(default)]
+    pub p99_confidence: PercentileWithConfidence,
+    #[serde(default)]
+    pub duration_seconds: f64,
+    pub status_codes: HashMap<u16, usize>, // HTTP statuses, 0 without a response
+    #[serde(default)]
+    pub grpc_status_codes: HashMap<u16, usize>, // Only on gRPC rows: gRPC codes (0 = OK), set client-side without a response
+    #[serde(default)]
+    pub slowest_requests: Vec<SlowRequest>, // Slowest first, with the IDs needed to find them in a tracing backend
+    #[serde(default)]
+    pub latency_histogram: Vec<(u64, usize)>, // (bin start ms, requests), `HISTOGRAM_BINS` equal-width bins
+    #[serde(default)]
+    pub percentile_spectrum: Vec<(f64, u64)>, // (percentile, latency ms) at each of `PERCENTILE_SPECTRUM`
+    #[serde(default)]
+    pub tls: Option<TlsSession>, // None for plain HTTP, through a proxy or when the handshake failed
+    #[serde(default)]
+    pub addresses: Vec<AddressResult>, // Per target address when the host has a `resolve` override
+    #[serde(default)]
+    pub source_addresses: Vec<AddressResult>, // Per local address when requests are sent from a pool of them
+    #[serde(default)]
+    pub websocket: Option<WebSocketStats>, // Only on WebSocket rows, whose requests and latencies are messages and round trips
+    #[serde(default)]
+    pub event_stream: Option<EventStreamStats>, // Only on SSE and long-poll rows, whose requests and latencies are events and gaps
+}
+
+/// Connection-level results for a WebSocket endpoint row.
+///
+/// The row's requests are messages sent plus failed connects and unrendered messages, its latencies are
+/// message round trips and failed connects, and its status codes are handshake outcomes, one per connection (101 when upgraded).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WebSocketStats {
+    pub connections: usize, // Attempted
+    pub connect_failures: usize,
+    pub mean_connect_ms: f64, // TCP connect, TLS and upgrade handshake, over successful connects
+    pub p95_connect_ms: u64,
+    pub messages_sent: usize,
+    pub replies: usize,
+    pub messages_per_second: f64, // Replies per second across all connections
+    pub disconnects: usize,       // Connections closed by the server or dropped before their script finished
+    #[serde(default)]
+    pub render_failures: usize, // Messages not sent because their template failed to render; errors without a latency
+}
+
+/// Subscriber-level results for an SSE or long-poll endpoint row.
+///
+/// The row's requests are events received plus failed subscriptions and disconnects, its latencies
+/// are the gaps before each event, and its status codes are subscription outcomes, one per subscriber.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EventStreamStats {
+    pub subscribers: usize, // Attempted
+    pub subscribe_failures: usize,
+    pub hold_seconds: f64,
+    pub mean_subscribe_ms: f64,   // Until the stream's response headers, or the first poll's response
+    pub mean_first_event_ms: f64, // From subscribing, over subscribers that got an event
+    pub p95_first_event_ms: u64,
+    pub events: usize,
+    pub events_per_second: f64, // Across all subscribers
+    pub mean_gap_ms: f64,       // Between consecutive events on a subscriber
+    pub p95_gap_ms: u64,
+    pub disconnects: usize, // Streams closed by the server or failed before their hold ended
+}
+
+/// Results for the requests an endpoint sent to, or from, one address
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AddressResult {
+    pub address: IpAddr,
+    pub total_requests: usize,
+    pub failed_requests: usize,
+    pub success_rate: f64,
     pub mean_latency: f64,
     pub p95_latency: u64,
     pub p99_latency: u64,
-    pub status_codes: HashMap<u16, usize>,
+}
+
+/// TLS session a probe handshake negotiated with an endpoint, using the endpoint's TLS settings
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TlsSession {
+    pub version: String,      // e.g. "TLS 1.3"
+    pub cipher_suite: String, // IANA-style name, e.g. "TLS13_AES_128_GCM_SHA256"
+}
+
+/// One of the slowest requests to an endpoint
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SlowRequest {
+    pub sent_at: DateTime<Local>,
+    pub latency_ms: u64,
+    pub status: Option<u16>, // None when no response was received
+    pub trace_id: Option<String>,
+    pub request_id: Option<String>,
+}
+
+/// Aggregates for one fixed interval of a scenario, keyed by completion time
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TimeSeriesBucket {
+    pub offset_seconds: f64, // Interval start relative to the scenario start
+    pub requests: usize,
+    pub errors: usize,
+    pub rps: f64,
+    pub active_connections: usize, // Peak in-flight requests seen in the interval
+    pub mean_latency: f64,
+    pub p50_latency: u64,
+    pub p95_latency: u64,
+    pub p99_latency: u64,
+    #[serde(default)]
+    pub latency_buckets: Vec<usize>, // Requests per `LATENCY_BUCKETS_MS` bound, then above the last bound
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -32,11 +178,33 @@ pub struct ScenarioResult {
     pub mean_latency: f64,
     pub p95_latency: u64,
     pub p99_latency: u64,
+    #[serde(default)]
+    pub p95_confidence: PercentileWithConfidence,
+    #[serde(default)]
+    pub p99_confidence: PercentileWithConfidence,
     pub duration_seconds: f64,
     pub endpoints: Vec<EndpointResult>,
+    #[serde(default)]
+    pub time_series: Vec<TimeSeriesBucket>,
+    #[serde(default)]
+    pub percentile_spectrum: Vec<(f64, u64)>,
 }
 
-#[derive(Serialize, Deserialize)]
+/// One completed request, streamed to `LoadTestConfig::record_requests` as it finishes
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RequestRecord {
+    pub timestamp: DateTime<Local>, // When the request was sent
+    pub scenario: usize,            // 1-based scenario index
+    pub concurrency: usize,
+    pub endpoint: String,
+    pub status: Option<u16>, // None when no response was received
+    pub latency_ms: u64,
+    pub bytes: u64, // Response body size
+    pub error_class: Option<ErrorClass>,
+    pub grpc_code: Option<u16>, // gRPC status code on gRPC rows
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct LoadTestReport {
     pub test_start_time: DateTime<Local>,
     pub test_end_time: DateTime<Local>,
@@ -51,4 +219,78 @@ pub struct LoadTestReport {
     pub scenarios: Vec<ScenarioResult>,
     pub base_url: String,
     pub endpoints_tested: Vec<String>,
+    #[serde(default)]
+    pub http_client: Option<HttpClientConfig>, // Effective client settings; None when rebuilt from a request log
+    #[serde(default)]
+    pub endpoint_http_clients: HashMap<String, HttpClientConfig>,
+    #[serde(default)]
+    pub dropped_log_entries: u64, // Rebuilt from a request log whose oldest entries were rotated away; the report is partial
+}
+
+/// Small hand-built results for the report, export and sink tests
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::*;
+    use crate::load_tester::build_report;
+    use std::time::Duration;
+
+    pub(crate) fn endpoint(name: &str, total: usize, failed: usize, p95_latency: u64) -> EndpointResult {
+        EndpointResult {
+            endpoint: name.to_string(),
+            total_requests: total,
+            successful_requests: total - failed,
+            failed_requests: failed,
+            success_rate: if total > 0 { (total - failed) as f64 / total as f64 * 100.0 } else { 0.0 },
+            rps: total as f64 / 2.0,
+            mean_latency: p95_latency as f64 / 2.0,
+            p95_latency,
+            p99_latency: p95_latency * 2,
+            p95_confidence: PercentileWithConfidence::default(),
+            p99_confidence: PercentileWithConfidence::default(),
+            duration_seconds: 2.0,
+            status_codes: HashMap::from([(200, total - failed), (500, failed)]),
+            grpc_status_codes: HashMap::new(),
+            slowest_requests: Vec::new(),
+            latency_histogram: Vec::new(),
+            percentile_spectrum: Vec::new(),
+            tls: None,
+            addresses: Vec::new(),
+            source_addresses: Vec::new(),
+            websocket: None,
+            event_stream: None,
+        }
+    }
+
+    pub(crate) fn scenario(concurrency: usize, endpoints: Vec<EndpointResult>) -> ScenarioResult {
+        let total: usize = endpoints.iter().map(|e| e.total_requests).sum();
+        let failed: usize = endpoints.iter().map(|e| e.failed_requests).sum();
+        ScenarioResult {
+            concurrency,
+            total_requests: total,
+            successful_requests: total - failed,
+            failed_requests: failed,
+            success_rate: if total > 0 { (total - failed) as f64 / total as f64 * 100.0 } else { 0.0 },
+            rps: total as f64 / 2.0,
+            mean_latency: endpoints.iter().map(|e| e.mean_latency).fold(0.0, f64::max),
+            p95_latency: endpoints.iter().map(|e| e.p95_latency).max().unwrap_or(0),
+            p99_latency: endpoints.iter().map(|e| e.p99_latency).max().unwrap_or(0),
+            p95_confidence: PercentileWithConfidence::default(),
+            p99_confidence: PercentileWithConfidence::default(),
+            duration_seconds: 2.0,
+            endpoints,
+            time_series: Vec::new(),
+            percentile_spectrum: Vec::new(),
+        }
+    }
+
+    pub(crate) fn report(scenarios: Vec<ScenarioResult>) -> LoadTestReport {
+        let mut endpoints: Vec<String> = Vec::new();
+        for endpoint in scenarios.iter().flat_map(|s| &s.endpoints) {
+            if !endpoints.contains(&endpoint.endpoint) {
+                endpoints.push(endpoint.endpoint.clone());
+            }
+        }
+        let start = DateTime::parse_from_rfc3339("2024-05-01T12:00:00+00:00").unwrap().with_timezone(&Local);
+        build_report("http://api.test", start, Duration::from_secs(4), scenarios, &endpoints)
+    }
 }
@@ -0,0 +1,127 @@
+use crate::live::{LiveMonitor, RunStage, LATENCY_BUCKETS_MS};
+use std::fmt::Write;
+
+/// Current run state in the Prometheus text exposition format (version 0.0.4)
+pub fn render_prometheus_metrics(monitor: &LiveMonitor) -> String {
+    let snapshot = monitor.snapshot();
+    let mut out = String::new();
+
+    let stage = match snapshot.stage {
+        RunStage::Starting => "starting",
+        RunStage::Running => "running",
+        RunStage::Paused => "paused",
+        RunStage::Skipping => "skipping",
+        RunStage::Aborting => "aborting",
+        RunStage::Finished => "finished",
+    };
+    header(&mut out, "load_test_info", "gauge", "Current scenario and stage of the run");
+    let _ = writeln!(
+        out,
+        "load_test_info{{stage=\"{}\",scenario=\"{}\",concurrency=\"{}\",endpoint=\"{}\"}} 1",
+        stage,
+        snapshot.scenario_index,
+        snapshot.concurrency,
+        escape_label(&snapshot.current_endpoint)
+    );
+
+    gauge(&mut out, "load_test_scenario", "Current scenario (1-based, 0 before the first)", snapshot.scenario_index as f64);
+    gauge(&mut out, "load_test_scenario_count", "Scenarios in the run", snapshot.scenario_count as f64);
+    gauge(&mut out, "load_test_concurrency", "Concurrency of the current scenario", snapshot.concurrency as f64);
+    gauge(
+        &mut out,
+        "load_test_in_flight_requests",
+        "Requests sent and not yet completed, plus open WebSocket connections and event stream subscriptions",
+        snapshot.in_flight as f64,
+    );
+    gauge(&mut out, "load_test_planned_requests", "Requests planned for the whole run", snapshot.planned_requests as f64);
+    gauge(&mut out, "load_test_target_rps", "Submission rate ceiling for the current endpoint", snapshot.target_rps);
+    gauge(&mut out, "load_test_achieved_rps", "Completed requests per second over the rolling window", snapshot.rolling_rps);
+
+    header(
+        &mut out,
+        "load_test_requests_total",
+        "counter",
+        "Completed requests by endpoint, HTTP status (0 = no response), gRPC code on gRPC calls and error class",
+    );
+    for (key, count) in monitor.request_counts() {
+        let grpc_code = key.grpc_code.map(|code| format!(",grpc_code=\"{}\"", code)).unwrap_or_default();
+        let _ = writeln!(
+            out,
+            "load_test_requests_total{{endpoint=\"{}\",status=\"{}\"{},error_class=\"{}\"}} {}",
+            escape_label(&key.endpoint),
+            key.status,
+            grpc_code,
+            key.error_class.map(|class| class.as_str()).unwrap_or("none"),
+            count
+        );
+    }
+
+    header(&mut out, "load_test_request_duration_seconds", "histogram", "Time from send to response headers");
+    for (endpoint, histogram) in monitor.latency_histograms() {
+        let endpoint = escape_label(&endpoint);
+        for (bound, count) in LATENCY_BUCKETS_MS.iter().zip(histogram.bucket_counts.iter()) {
+            let _ = writeln!(
+                out,
+                "load_test_request_duration_seconds_bucket{{endpoint=\"{}\",le=\"{}\"}} {}",
+                endpoint,
+                *bound as f64 / 1000.0,
+                count
+            );
+        }
+        let _ = writeln!(out, "load_test_request_duration_seconds_bucket{{endpoint=\"{}\",le=\"+Inf\"}} {}", endpoint, histogram.count);
+        let _ = writeln!(out, "load_test_request_duration_seconds_sum{{endpoint=\"{}\"}} {}", endpoint, histogram.sum_ms as f64 / 1000.0);
+        let _ = writeln!(out, "load_test_request_duration_seconds_count{{endpoint=\"{}\"}} {}", endpoint, histogram.count);
+    }
+
+    out
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, value: f64) {
+    header(out, name, "gauge", help);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ErrorClass;
+
+    #[test]
+    fn request_counts_keep_http_status_and_grpc_code_apart() {
+        let monitor = LiveMonitor::new();
+        monitor.start_run(1, 3);
+        monitor.request_started();
+        monitor.request_ended();
+        monitor.request_finished("/items", Some(200), None, 12, None);
+        monitor.request_started();
+        monitor.request_ended();
+        monitor.request_finished("gRPC goals.Goals/GetGoal", Some(200), Some(5), 8, Some(ErrorClass::ClientError));
+        monitor.request_started();
+        monitor.request_ended();
+        monitor.request_finished("gRPC goals.Goals/GetGoal", None, Some(14), 3, Some(ErrorClass::Connect));
+
+        let metrics = render_prometheus_metrics(&monitor);
+        assert!(metrics.contains("load_test_requests_total{endpoint=\"/items\",status=\"200\",error_class=\"none\"} 1"));
+        assert!(metrics.contains(
+            "load_test_requests_total{endpoint=\"gRPC goals.Goals/GetGoal\",status=\"200\",grpc_code=\"5\",error_class=\"client_error\"} 1"
+        ));
+        assert!(metrics.contains(
+            "load_test_requests_total{endpoint=\"gRPC goals.Goals/GetGoal\",status=\"0\",grpc_code=\"14\",error_class=\"connect\"} 1"
+        ));
+        assert!(metrics.contains("load_test_in_flight_requests 0"));
+    }
+
+    #[test]
+    fn labels_are_escaped() {
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
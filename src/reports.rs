@@ -1,147 +1,136 @@
-use crate::models::LoadTestReport;
-use std::fs::File;
+use crate::export::{save_csv_tables, DEFAULT_CSV_DIR};
+use crate::junit::Thresholds;
+use crate::live::LATENCY_BUCKETS_MS;
+use crate::metrics::PERCENTILE_SPECTRUM;
+use crate::models::{LoadTestReport, ScenarioResult};
+use crate::svg_charts::{self, Series};
+use crate::templates::{render_template, DEFAULT_HTML_TEMPLATE, DEFAULT_MARKDOWN_TEMPLATE};
+use minijinja::{context, Value};
+use serde::Serialize;
+use std::fs::{self, File};
 use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::info;
 
-impl LoadTestReport {
-    pub fn generate_html(&self) -> String {
-        let chart_data = self.generate_chart_data();
-        
-        format!(r#"<!DOCTYPE html>
-<html>
-<head>
-    <meta charset="UTF-8">
-    <title>Load Test Report</title>
-    <style>
-        body {{ font-family: Arial, sans-serif; margin: 40px; line-height: 1.6; }}
-        .header {{ text-align: center; border-bottom: 2px solid #333; padding-bottom: 20px; }}
-        .summary-table {{ border-collapse: collapse; width: 100%; margin: 20px 0; }}
-        .summary-table th, .summary-table td {{ border: 1px solid #ddd; padding: 12px; text-align: left; }}
-        .summary-table th {{ background-color: #f2f2f2; }}
-        .section {{ margin: 30px 0; }}
-        .endpoint-table {{ border-collapse: collapse; width: 100%; font-size: 12px; }}
-        .endpoint-table th, .endpoint-table td {{ border: 1px solid #ddd; padding: 8px; text-align: left; }}
-        .endpoint-table th {{ background-color: #f2f2f2; }}
-        .metrics {{ display: flex; justify-content: space-around; margin: 20px 0; }}
-        .metric-box {{ border: 1px solid #ddd; padding: 15px; text-align: center; background-color: #f9f9f9; }}
-        .chart-container {{ border: 1px solid #ddd; margin: 20px 0; padding: 20px; background-color: #f9f9f9; }}
-    </style>
-</head>
-<body>
-    <div class="header">
-        <h1>Load Test Report</h1>
-        <p>Generated on: {}</p>
-        <p>Test Duration: {:.2} seconds</p>
-    </div>
+/// How charts are rendered in the HTML report
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ChartMode {
+    /// Inline SVG rendered at report time; the report is a single offline file
+    #[default]
+    Embedded,
+    /// Chart.js loaded from cdn.jsdelivr.net; smaller file, needs network access to render
+    Cdn,
+}
 
-    <div class="section">
-        <h2>1. Test Objectives</h2>
-        <p>This load test was conducted to evaluate the performance and scalability of the API endpoints under various load conditions. The test aimed to:</p>
-        <ul>
-            <li>Determine the maximum sustainable request rate</li>
-            <li>Measure response times under different concurrency levels</li>
-            <li>Identify potential bottlenecks and failure points</li>
-            <li>Validate system stability under stress conditions</li>
-        </ul>
-    </div>
+#[derive(Clone, Default)]
+pub struct ReportOptions {
+    pub chart_mode: ChartMode,
+    pub html_template: Option<PathBuf>,     // Replaces the built-in HTML template
+    pub markdown_template: Option<PathBuf>, // Replaces the built-in Markdown template
+    pub thresholds: Thresholds,             // Checked by the JUnit XML report
+}
 
-    <div class="section">
-        <h2>2. Test Configuration</h2>
-        <table class="summary-table">
-            <tr><th>Parameter</th><th>Value</th></tr>
-            <tr><td>Base URL</td><td>{}</td></tr>
-            <tr><td>Total Endpoints Tested</td><td>{}</td></tr>
-            <tr><td>Test Start Time</td><td>{}</td></tr>
-            <tr><td>Test End Time</td><td>{}</td></tr>
-        </table>
-    </div>
+/// Degradation criteria between consecutive scenarios used to find the breaking point
+#[derive(Debug, Clone, Serialize)]
+pub struct ScalingThresholds {
+    pub min_success_rate: f64,  // Percent
+    pub min_rps_ratio: f64,     // Current RPS / previous RPS
+    pub max_latency_ratio: f64, // Current mean latency / previous mean latency
+}
 
-    <div class="section">
-        <h2>3. Overall Results Summary</h2>
-        <div class="metrics">
-            <div class="metric-box">
-                <h3>Total Requests</h3>
-                <h2>{}</h2>
-            </div>
-            <div class="metric-box">
-                <h3>Success Rate</h3>
-                <h2>{:.2}%</h2>
-            </div>
-            <div class="metric-box">
-                <h3>Average RPS</h3>
-                <h2>{:.2}</h2>
-            </div>
-            <div class="metric-box">
-                <h3>Mean Latency</h3>
-                <h2>{:.2}ms</h2>
-            </div>
-        </div>
+pub const SCALING_THRESHOLDS: ScalingThresholds = ScalingThresholds {
+    min_success_rate: 95.0,
+    min_rps_ratio: 0.8,     // 20% RPS drop
+    max_latency_ratio: 1.5, // 50% latency increase
+};
 
-        <table class="summary-table">
-            <tr><th>Metric</th><th>Value</th></tr>
-            <tr><td>Total Requests</td><td>{}</td></tr>
-            <tr><td>Successful Requests</td><td>{}</td></tr>
-            <tr><td>Failed Requests</td><td>{}</td></tr>
-            <tr><td>Success Rate</td><td>{:.2}%</td></tr>
-            <tr><td>Average RPS</td><td>{:.2} req/sec</td></tr>
-            <tr><td>Mean Latency</td><td>{:.2}ms</td></tr>
-            <tr><td>95th Percentile Latency</td><td>{}ms</td></tr>
-            <tr><td>99th Percentile Latency</td><td>{}ms</td></tr>
-        </table>
-    </div>
+/// Computed scaling analysis exposed to report templates as `analysis`
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportAnalysis {
+    pub min_concurrency: usize,
+    pub max_concurrency: usize,
+    pub successful_requests: usize,
+    pub best_performing_concurrency: usize,
+    pub breaking_point: usize,
+    pub optimal_range_start: usize,
+    pub optimal_range_end: usize,
+    pub scalability_factor: f64,
+    pub linear_scaling: bool,
+    pub bottleneck_analysis: String,
+    pub performance_scores: Vec<f64>, // One per scenario, in `report.scenarios` order
+    pub thresholds: ScalingThresholds,
+}
 
-    <div class="section">
-        <h2>4. Scaling Analysis & Comparison</h2>
-        {}
-        
-        <h3>Performance Trends</h3>
-        <table class="summary-table">
-            <tr>
-                <th>Concurrency</th>
-                <th>Total Requests</th>
-                <th>Success Rate (%)</th>
-                <th>RPS</th>
-                <th>Mean Latency (ms)</th>
-                <th>P95 Latency (ms)</th>
-                <th>P99 Latency (ms)</th>
-                <th>Duration (s)</th>
-                <th>Performance Score</th>
-            </tr>
-            {}
-        </table>
-        
-        <h3>Key Performance Insights</h3>
-        <div class="metrics">
-            <div class="metric-box">
-                <h4>Best Performing Concurrency</h4>
-                <h3>{}</h3>
-                <p>Highest RPS with good latency</p>
-            </div>
-            <div class="metric-box">
-                <h4>Breaking Point</h4>
-                <h3>{}</h3>
-                <p>Where auto-scaling limits are reached</p>
-            </div>
-            <div class="metric-box">
-                <h4>Optimal Load Range</h4>
-                <h3>{} - {}</h3>
-                <p>Recommended operating range</p>
-            </div>
-            <div class="metric-box">
-                <h4>Scalability Factor</h4>
-                <h3>{:.1}x</h3>
-                <p>Performance improvement ratio</p>
-            </div>
-        </div>
-    </div>
+impl LoadTestReport {
+    pub fn generate_html(&self) -> String {
+        self.generate_html_with_options(&ReportOptions::default())
+            .expect("built-in HTML template failed to render")
+    }
 
-    <div class="section">
-        <h2>5. Detailed Scenario Results</h2>
+    pub fn generate_html_with_options(&self, options: &ReportOptions) -> Result<String, Box<dyn std::error::Error>> {
+        let (charts_html, chart_scripts) = match options.chart_mode {
+            ChartMode::Embedded => (self.generate_svg_charts_html(), String::new()),
+            ChartMode::Cdn => {
+                let mut chart_data = self.generate_chart_data();
+                chart_data.push_str(&self.generate_time_series_chart_data());
+                // Chart.js has no heatmap, so the distribution charts stay inline SVG
+                (
+                    self.generate_canvas_charts_html() + &self.generate_distribution_charts_html(),
+                    format!(r#"
+    <script src="https://cdn.jsdelivr.net/npm/chart.js"></script>
+    <script>
         {}
-    </div>
+    </script>"#, chart_data),
+                )
+            }
+        };
 
-    <div class="section">
-        <h2>6. Performance Charts</h2>
-        
+        let source = load_template(options.html_template.as_ref(), DEFAULT_HTML_TEMPLATE)?;
+        let context = context! {
+            report => self,
+            analysis => self.analysis(),
+            charts => Value::from_safe_string(charts_html),
+            chart_scripts => Value::from_safe_string(chart_scripts),
+        };
+        Ok(render_template("report.html", &source, context)?)
+    }
+
+    pub fn generate_markdown(&self) -> String {
+        self.generate_markdown_with_options(&ReportOptions::default())
+            .expect("built-in Markdown template failed to render")
+    }
+
+    pub fn generate_markdown_with_options(&self, options: &ReportOptions) -> Result<String, Box<dyn std::error::Error>> {
+        let source = load_template(options.markdown_template.as_ref(), DEFAULT_MARKDOWN_TEMPLATE)?;
+        let context = context! {
+            report => self,
+            analysis => self.analysis(),
+        };
+        Ok(render_template("report.md", &source, context)?)
+    }
+
+    pub fn analysis(&self) -> ReportAnalysis {
+        let (optimal_range_start, optimal_range_end) = self.get_optimal_range();
+        ReportAnalysis {
+            min_concurrency: self.scenarios.first().map(|s| s.concurrency).unwrap_or(0),
+            max_concurrency: self.scenarios.last().map(|s| s.concurrency).unwrap_or(0),
+            successful_requests: self.overall_requests - self.overall_errors,
+            best_performing_concurrency: self.get_best_performing_concurrency(),
+            breaking_point: self.get_breaking_point(),
+            optimal_range_start,
+            optimal_range_end,
+            scalability_factor: self.get_scalability_factor(),
+            linear_scaling: self.is_linear_scaling(),
+            bottleneck_analysis: self.get_bottleneck_analysis(),
+            performance_scores: self.scenarios.iter()
+                .map(|scenario| self.calculate_performance_score(scenario))
+                .collect(),
+            thresholds: SCALING_THRESHOLDS,
+        }
+    }
+
+    fn generate_canvas_charts_html(&self) -> String {
+        format!(r#"
         <div class="chart-container">
             <h3>Scaling Performance Overview (Latency vs RPS)</h3>
             <canvas id="scalingChart" width="800" height="400"></canvas>
@@ -171,301 +160,345 @@ impl LoadTestReport {
             <h3>Overall Test Results</h3>
             <canvas id="overallChart" width="400" height="400"></canvas>
         </div>
-    </div>
-    
-    <script src="https://cdn.jsdelivr.net/npm/chart.js"></script>
-    <script>
-        {}
-    </script>
-</body>
-</html>"#,
-            self.test_end_time.format("%Y-%m-%d %H:%M:%S"),
-            self.total_duration_seconds,
-            self.base_url,
-            self.endpoints_tested.len(),
-            self.test_start_time.format("%Y-%m-%d %H:%M:%S"),
-            self.test_end_time.format("%Y-%m-%d %H:%M:%S"),
-            self.overall_requests,
-            self.overall_success_rate,
-            self.overall_rps,
-            self.overall_mean_latency,
-            self.overall_requests,
-            self.overall_requests - self.overall_errors,
-            self.overall_errors,
-            self.overall_success_rate,
-            self.overall_rps,
-            self.overall_mean_latency,
-            self.overall_p95_latency,
-            self.overall_p99_latency,
-            self.generate_scaling_summary(),
-            self.generate_scaling_comparison_table(),
-            self.get_best_performing_concurrency(),
-            self.get_breaking_point(),
-            {
-                let (start, _end) = self.get_optimal_range();
-                format!("{}", start)
-            },
-            {
-                let (_start, end) = self.get_optimal_range();
-                format!("{}", end)
-            },
-            self.get_scalability_factor(),
-            self.generate_scenario_html(),
-            chart_data
+        {}"#,
+            self.generate_time_series_html()
         )
     }
 
-    fn generate_scenario_html(&self) -> String {
+    fn generate_svg_charts_html(&self) -> String {
+        let concurrency_labels: Vec<String> = self.scenarios.iter()
+            .map(|s| s.concurrency.to_string())
+            .collect();
         let mut html = String::new();
-        
-        for (index, scenario) in self.scenarios.iter().enumerate() {
-            html.push_str(&format!(r#"
-        <h3>Scenario {} - Concurrency: {}</h3>
-        <table class="summary-table">
-            <tr><th>Metric</th><th>Value</th></tr>
-            <tr><td>Concurrency</td><td>{}</td></tr>
-            <tr><td>Total Requests</td><td>{}</td></tr>
-            <tr><td>Success Rate</td><td>{:.2}%</td></tr>
-            <tr><td>RPS</td><td>{:.2}</td></tr>
-            <tr><td>Mean Latency</td><td>{:.2}ms</td></tr>
-            <tr><td>P95 Latency</td><td>{}ms</td></tr>
-            <tr><td>Duration</td><td>{:.2}s</td></tr>
-        </table>
-
-        <h4>Endpoint Details:</h4>
-        <table class="endpoint-table">
-            <tr>
-                <th>Endpoint</th>
-                <th>Requests</th>
-                <th>Success</th>
-                <th>Errors</th>
-                <th>Success Rate</th>
-                <th>Mean Latency</th>
-                <th>P95 Latency</th>
-            </tr>
-            {}
-        </table>
-        "#,
-                index + 1,
-                scenario.concurrency,
-                scenario.concurrency,
-                scenario.total_requests,
-                scenario.success_rate,
-                scenario.rps,
-                scenario.mean_latency,
-                scenario.p95_latency,
-                scenario.duration_seconds,
-                scenario.endpoints.iter().map(|ep| {
-                    format!(r#"
-            <tr>
-                <td>{}</td>
-                <td>{}</td>
-                <td>{}</td>
-                <td>{}</td>
-                <td>{:.2}%</td>
-                <td>{:.2}ms</td>
-                <td>{}ms</td>
-            </tr>"#,
-                        ep.endpoint,
-                        ep.total_requests,
-                        ep.successful_requests,
-                        ep.failed_requests,
-                        ep.success_rate,
-                        ep.mean_latency,
-                        ep.p95_latency
-                    )
-                }).collect::<Vec<_>>().join("")
-            ));
-        }
-        
-        html
-    }
-
-    pub fn generate_markdown(&self) -> String {
-        format!(r#"# Load Test Report - Scaling Analysis
-
-*Generated on: {}*  
-*Test Duration: {:.2} seconds*
 
-## 1. Test Objectives
+        html.push_str(&chart_container(
+            "Scaling Performance Overview (Latency vs RPS)",
+            svg_charts::scatter_chart(
+                "Scaling Performance: RPS vs Latency by Concurrency",
+                &self.scenarios.iter()
+                    .map(|s| (s.rps, s.mean_latency, format!("Concurrency {}", s.concurrency)))
+                    .collect::<Vec<_>>(),
+                "Requests Per Second (RPS)",
+                "Mean Latency (ms)",
+            ),
+        ));
 
-This load test was conducted to evaluate the performance and scalability of the API endpoints under various load conditions. The test aimed to:
+        html.push_str(&chart_container(
+            "Latency Scaling by Concurrency",
+            svg_charts::line_chart(
+                "Response Time Scaling by Concurrency Level",
+                &concurrency_labels,
+                "Concurrent Connections",
+                ("Latency (milliseconds)", &[
+                    Series::new("Mean Latency (ms)", self.scenarios.iter().map(|s| s.mean_latency).collect(), svg_charts::PRIMARY),
+                    Series::new("P95 Latency (ms)", self.scenarios.iter().map(|s| s.p95_latency as f64).collect(), svg_charts::WARNING),
+                ]),
+                None,
+            ),
+        ));
 
-- Determine the maximum sustainable request rate
-- Measure response times under different concurrency levels  
-- Identify potential bottlenecks and failure points
-- Validate system stability under stress conditions
-- Analyze scaling characteristics from {} to {} concurrent connections
+        html.push_str(&chart_container(
+            "Throughput Scaling (RPS by Concurrency)",
+            svg_charts::bar_chart(
+                "Throughput Scaling (RPS by Concurrency)",
+                &concurrency_labels,
+                &self.scenarios.iter().map(|s| s.rps).collect::<Vec<_>>(),
+                svg_charts::SUCCESS,
+                "Concurrent Connections",
+                "Requests Per Second",
+            ),
+        ));
 
-## 2. Test Configuration
+        html.push_str(&chart_container(
+            "Success Rate by Concurrency Level",
+            svg_charts::line_chart(
+                "Success Rate by Concurrency Level",
+                &concurrency_labels,
+                "Concurrent Connections",
+                ("Success Rate (%)", &[
+                    Series::new("Success Rate (%)", self.scenarios.iter().map(|s| s.success_rate).collect(), svg_charts::SUCCESS),
+                ]),
+                None,
+            ),
+        ));
 
-| Parameter | Value |
-|-----------|-------|
-| Base URL | {} |
-| Total Endpoints Tested | {} |
-| Scaling Levels | {} scenarios |
-| Concurrency Range | {} - {} connections |
-| Total Requests | {} |
-| Test Start Time | {} |
-| Test End Time | {} |
-
-## 3. Scaling Analysis & Performance Summary
-
-{}
-
-### Performance Trends
-
-| Concurrency | Total Requests | Success Rate (%) | RPS | Mean Latency (ms) | P95 Latency (ms) | Duration (s) | Performance Score |
-|-------------|----------------|------------------|-----|-------------------|------------------|--------------|-------------------|
-{}
-
-### Key Performance Insights
-
-- **Best Performing Concurrency:** {} connections
-- **Breaking Point:** {} connections  
-- **Optimal Load Range:** {} - {} connections
-- **Scalability Factor:** {:.1}x improvement from baseline to peak
-- **Linear Scaling:** {}
-
-## 4. Overall Results Summary
-
-### Key Metrics
-- **Total Requests:** {}
-- **Success Rate:** {:.2}%
-- **Average RPS:** {:.2}
-- **Mean Latency:** {:.2}ms
+        let best_scenario = self.scenarios.iter()
+            .max_by(|a, b| a.rps.partial_cmp(&b.rps).unwrap_or(std::cmp::Ordering::Equal));
+        if let Some(best_scenario) = best_scenario {
+            html.push_str(&chart_container(
+                "Endpoint Response Times (Best Scenario)",
+                svg_charts::bar_chart(
+                    &format!("Endpoint Response Times (Best Performing Scenario: {} concurrent)", best_scenario.concurrency),
+                    &best_scenario.endpoints.iter()
+                        .map(|ep| ep.endpoint.chars().take(30).collect())
+                        .collect::<Vec<_>>(),
+                    &best_scenario.endpoints.iter().map(|ep| ep.mean_latency).collect::<Vec<_>>(),
+                    svg_charts::PRIMARY,
+                    "API Endpoints",
+                    "Mean Latency (milliseconds)",
+                ),
+            ));
+        }
 
-### Detailed Results
+        html.push_str(&chart_container(
+            "Overall Test Results",
+            svg_charts::doughnut_chart(
+                "Overall Test Results Summary",
+                &[
+                    ("Successful Requests", (self.overall_requests - self.overall_errors) as f64, svg_charts::SUCCESS),
+                    ("Failed Requests", self.overall_errors as f64, svg_charts::DANGER),
+                ],
+            ),
+        ));
 
-| Metric | Value |
-|--------|-------|
-| Total Requests | {} |
-| Successful Requests | {} |
-| Failed Requests | {} |
-| Success Rate | {:.2}% |
-| Average RPS | {:.2} req/sec |
-| Mean Latency | {:.2}ms |
-| 95th Percentile Latency | {}ms |
-| 99th Percentile Latency | {}ms |
+        for (index, scenario) in self.scenarios.iter().enumerate() {
+            if scenario.time_series.is_empty() {
+                continue;
+            }
+            let series = scenario.time_series.as_slice();
+            html.push_str(&chart_container(
+                &format!("Time Series - Scenario {} (Concurrency: {})", index + 1, scenario.concurrency),
+                svg_charts::line_chart(
+                    &format!("Scenario {} over Time (Concurrency: {})", index + 1, scenario.concurrency),
+                    &series.iter().map(|b| format!("{}", b.offset_seconds)).collect::<Vec<_>>(),
+                    "Elapsed Time (s)",
+                    ("Requests / Errors / Connections", &[
+                        Series::new("RPS", series.iter().map(|b| b.rps).collect(), svg_charts::SUCCESS),
+                        Series::new("Errors", series.iter().map(|b| b.errors as f64).collect(), svg_charts::DANGER),
+                        Series::new("Active Connections", series.iter().map(|b| b.active_connections as f64).collect(), svg_charts::SECONDARY),
+                    ]),
+                    Some(("Latency (milliseconds)", &[
+                        Series::new("P50 Latency (ms)", series.iter().map(|b| b.p50_latency as f64).collect(), svg_charts::PRIMARY),
+                        Series::new("P95 Latency (ms)", series.iter().map(|b| b.p95_latency as f64).collect(), svg_charts::WARNING),
+                        Series::new("P99 Latency (ms)", series.iter().map(|b| b.p99_latency as f64).collect(), svg_charts::INFO),
+                    ])),
+                ),
+            ));
+        }
 
-## 5. Detailed Scenario Results
+        html.push_str(&self.generate_distribution_charts_html());
+        html
+    }
 
-{}
+    /// Percentile spectrum, time x latency heatmaps and per-endpoint histograms (always SVG)
+    fn generate_distribution_charts_html(&self) -> String {
+        let mut html = String::new();
+        let palette = [
+            svg_charts::PRIMARY,
+            svg_charts::SUCCESS,
+            svg_charts::WARNING,
+            svg_charts::DANGER,
+            svg_charts::INFO,
+            svg_charts::SECONDARY,
+        ];
 
-## 6. Recommendations
+        let spectrum_labels: Vec<String> = PERCENTILE_SPECTRUM.iter().map(|p| format!("p{}", p)).collect();
+        let spectrum_series: Vec<Series> = self.scenarios.iter()
+            .enumerate()
+            .filter(|(_, scenario)| !scenario.percentile_spectrum.is_empty())
+            .map(|(index, scenario)| Series::new(
+                &format!("Concurrency {}", scenario.concurrency),
+                scenario.percentile_spectrum.iter().map(|(_, latency)| *latency as f64).collect(),
+                palette[index % palette.len()],
+            ))
+            .collect();
+        if !spectrum_series.is_empty() {
+            html.push_str(&chart_container(
+                "Latency Percentile Spectrum",
+                svg_charts::line_chart(
+                    "Latency by Percentile (p50 to p99.999)",
+                    &spectrum_labels,
+                    "Percentile",
+                    ("Latency (milliseconds)", &spectrum_series),
+                    None,
+                ),
+            ));
+        }
 
-Based on the scaling analysis:
+        let mut bucket_labels: Vec<String> = LATENCY_BUCKETS_MS.iter().map(|bound| format!("≤{}ms", bound)).collect();
+        bucket_labels.push(format!(">{}ms", LATENCY_BUCKETS_MS[LATENCY_BUCKETS_MS.len() - 1]));
+        for (index, scenario) in self.scenarios.iter().enumerate() {
+            let series = scenario.time_series.as_slice();
+            if series.iter().all(|bucket| bucket.latency_buckets.is_empty()) {
+                continue;
+            }
+            let rows: Vec<Vec<usize>> = (0..bucket_labels.len())
+                .map(|row| series.iter().map(|bucket| bucket.latency_buckets.get(row).copied().unwrap_or(0)).collect())
+                .collect();
+            html.push_str(&chart_container(
+                &format!("Latency Heatmap - Scenario {} (Concurrency: {})", index + 1, scenario.concurrency),
+                svg_charts::heatmap_chart(
+                    &format!("Requests by Latency over Time (Concurrency: {})", scenario.concurrency),
+                    &series.iter().map(|b| format!("{}", b.offset_seconds)).collect::<Vec<_>>(),
+                    &bucket_labels,
+                    &rows,
+                    "Elapsed Time (s)",
+                    "Latency",
+                ),
+            ));
+        }
 
-1. **Optimal Operating Range:** {} - {} concurrent connections provide the best balance of throughput and latency
-2. **Performance Degradation:** Monitor closely beyond {} concurrent connections
-3. **Resource Planning:** System shows {}x scaling capability from baseline to peak performance
-4. **Bottleneck Analysis:** {}
+        for (index, scenario) in self.scenarios.iter().enumerate() {
+            let histograms: String = scenario.endpoints.iter()
+                .filter(|endpoint| !endpoint.latency_histogram.is_empty())
+                .map(|endpoint| svg_charts::bar_chart(
+                    &format!("{} (Concurrency: {})", endpoint.endpoint, scenario.concurrency),
+                    &endpoint.latency_histogram.iter().map(|(start, _)| start.to_string()).collect::<Vec<_>>(),
+                    &endpoint.latency_histogram.iter().map(|(_, count)| *count as f64).collect::<Vec<_>>(),
+                    svg_charts::PRIMARY,
+                    "Latency Bin Start (ms)",
+                    "Requests",
+                ))
+                .collect();
+            if !histograms.is_empty() {
+                html.push_str(&format!(r#"
+        <details class="chart-container">
+            <summary><h3 style="display: inline;">Endpoint Latency Histograms - Scenario {} (Concurrency: {})</h3></summary>
+            {}
+        </details>
+"#, index + 1, scenario.concurrency, histograms));
+            }
+        }
 
-"#,
-            self.test_end_time.format("%Y-%m-%d %H:%M:%S"),
-            self.total_duration_seconds,
-            self.scenarios.first().map(|s| s.concurrency).unwrap_or(0),
-            self.scenarios.last().map(|s| s.concurrency).unwrap_or(0),
-            self.base_url,
-            self.endpoints_tested.len(),
-            self.scenarios.len(),
-            self.scenarios.first().map(|s| s.concurrency).unwrap_or(0),
-            self.scenarios.last().map(|s| s.concurrency).unwrap_or(0),
-            self.overall_requests,
-            self.test_start_time.format("%Y-%m-%d %H:%M:%S"),
-            self.test_end_time.format("%Y-%m-%d %H:%M:%S"),
-            self.generate_scaling_summary_markdown(),
-            self.generate_scaling_table_markdown(),
-            self.get_best_performing_concurrency(),
-            self.get_breaking_point(),
-            {
-                let (start, end) = self.get_optimal_range();
-                start
-            },
-            {
-                let (start, end) = self.get_optimal_range();
-                end
-            },
-            self.get_scalability_factor(),
-            if self.is_linear_scaling() { "Yes" } else { "No" },
-            self.overall_requests,
-            self.overall_success_rate,
-            self.overall_rps,
-            self.overall_mean_latency,
-            self.overall_requests,
-            self.overall_requests - self.overall_errors,
-            self.overall_errors,
-            self.overall_success_rate,
-            self.overall_rps,
-            self.overall_mean_latency,
-            self.overall_p95_latency,
-            self.overall_p99_latency,
-            self.generate_scenario_markdown(),
-            {
-                let (start, _end) = self.get_optimal_range();
-                start
-            },
-            {
-                let (_start, end) = self.get_optimal_range();
-                end
-            },
-            self.get_scalability_factor(),
-            self.get_bottleneck_analysis(),
-            // Add the missing method call for the recommendations section
-            self.get_recommendations()
-        )
+        html
     }
 
-    fn generate_scenario_markdown(&self) -> String {
-        let mut markdown = String::new();
-        
-        for (index, scenario) in self.scenarios.iter().enumerate() {
-            markdown.push_str(&format!(r#"
-### Scenario {} - Concurrency: {}
+    fn generate_time_series_html(&self) -> String {
+        self.scenarios.iter()
+            .enumerate()
+            .filter(|(_, scenario)| !scenario.time_series.is_empty())
+            .map(|(index, scenario)| format!(r#"
+        <div class="chart-container">
+            <h3>Time Series - Scenario {} (Concurrency: {})</h3>
+            <canvas id="timeSeriesChart{}" width="800" height="400"></canvas>
+        </div>"#,
+                index + 1,
+                scenario.concurrency,
+                index
+            ))
+            .collect()
+    }
 
-| Metric | Value |
-|--------|-------|
-| Concurrency | {} |
-| Total Requests | {} |
-| Success Rate | {:.2}% |
-| RPS | {:.2} |
-| Mean Latency | {:.2}ms |
-| P95 Latency | {}ms |
-| Duration | {:.2}s |
+    fn generate_time_series_chart_data(&self) -> String {
+        let mut js = String::new();
 
-#### Endpoint Details:
+        for (index, scenario) in self.scenarios.iter().enumerate() {
+            if scenario.time_series.is_empty() {
+                continue;
+            }
+            let series = |value: &dyn Fn(&crate::models::TimeSeriesBucket) -> String| {
+                scenario.time_series.iter().map(value).collect::<Vec<_>>().join(", ")
+            };
 
-| Endpoint | Requests | Success | Errors | Success Rate | Mean Latency | P95 Latency |
-|----------|----------|---------|--------|--------------|--------------|-------------|
-| {} |
+            js.push_str(&format!(r#"
+        // Time Series Chart - Scenario {}
+        new Chart(document.getElementById('timeSeriesChart{}').getContext('2d'), {{
+            type: 'line',
+            data: {{
+                labels: [{}],
+                datasets: [{{
+                    label: 'RPS',
+                    data: [{}],
+                    borderColor: chartColors.success,
+                    yAxisID: 'y',
+                    tension: 0.1,
+                    fill: false
+                }}, {{
+                    label: 'Errors',
+                    data: [{}],
+                    borderColor: chartColors.danger,
+                    yAxisID: 'y',
+                    tension: 0.1,
+                    fill: false
+                }}, {{
+                    label: 'Active Connections',
+                    data: [{}],
+                    borderColor: chartColors.secondary,
+                    yAxisID: 'y',
+                    stepped: true,
+                    fill: false
+                }}, {{
+                    label: 'P50 Latency (ms)',
+                    data: [{}],
+                    borderColor: chartColors.primary,
+                    yAxisID: 'y1',
+                    tension: 0.1,
+                    fill: false
+                }}, {{
+                    label: 'P95 Latency (ms)',
+                    data: [{}],
+                    borderColor: chartColors.warning,
+                    yAxisID: 'y1',
+                    tension: 0.1,
+                    fill: false
+                }}, {{
+                    label: 'P99 Latency (ms)',
+                    data: [{}],
+                    borderColor: chartColors.info,
+                    yAxisID: 'y1',
+                    tension: 0.1,
+                    fill: false
+                }}]
+            }},
+            options: {{
+                responsive: true,
+                plugins: {{
+                    title: {{
+                        display: true,
+                        text: 'Scenario {} over Time (Concurrency: {})'
+                    }}
+                }},
+                scales: {{
+                    y: {{
+                        beginAtZero: true,
+                        position: 'left',
+                        title: {{
+                            display: true,
+                            text: 'Requests / Errors / Connections'
+                        }}
+                    }},
+                    y1: {{
+                        beginAtZero: true,
+                        position: 'right',
+                        grid: {{ drawOnChartArea: false }},
+                        title: {{
+                            display: true,
+                            text: 'Latency (milliseconds)'
+                        }}
+                    }},
+                    x: {{
+                        title: {{
+                            display: true,
+                            text: 'Elapsed Time (s)'
+                        }}
+                    }}
+                }}
+            }}
+        }});
 "#,
                 index + 1,
-                scenario.concurrency,
-                scenario.concurrency,
-                scenario.total_requests,
-                scenario.success_rate,
-                scenario.rps,
-                scenario.mean_latency,
-                scenario.p95_latency,
-                scenario.duration_seconds,
-                scenario.endpoints.iter().map(|ep| {
-                    format!("| {} | {} | {} | {} | {:.2}% | {:.2}ms | {}ms |",
-                        ep.endpoint,
-                        ep.total_requests,
-                        ep.successful_requests,
-                        ep.failed_requests,
-                        ep.success_rate,
-                        ep.mean_latency,
-                        ep.p95_latency
-                    )
-                }).collect::<Vec<_>>().join("\n")
+                index,
+                series(&|b| format!("\"{}\"", b.offset_seconds)),
+                series(&|b| format!("{:.2}", b.rps)),
+                series(&|b| b.errors.to_string()),
+                series(&|b| b.active_connections.to_string()),
+                series(&|b| b.p50_latency.to_string()),
+                series(&|b| b.p95_latency.to_string()),
+                series(&|b| b.p99_latency.to_string()),
+                index + 1,
+                scenario.concurrency
             ));
         }
-        
-        markdown
+
+        js
     }
 
     fn generate_chart_data(&self) -> String {
+        // An aborted run can finish before any scenario; the canvases then stay empty
+        if self.scenarios.is_empty() {
+            return String::new();
+        }
+
         // Extract scaling data for charts
         let concurrency_labels: Vec<String> = self.scenarios.iter()
             .map(|s| format!("\"{}\"", s.concurrency))
@@ -494,7 +527,7 @@ Based on the scaling analysis:
         
         let endpoint_data = best_scenario.endpoints.iter()
             .map(|ep| (
-                format!("\"{}\"", ep.endpoint.replace('"', "\\\"")[..ep.endpoint.len().min(30)].to_string()),
+                js_string(&ep.endpoint.chars().take(30).collect::<String>()),
                 ep.mean_latency.to_string(),
                 ep.success_rate.to_string()
             ))
@@ -788,46 +821,6 @@ Based on the scaling analysis:
         )
     }
 
-    fn generate_scaling_summary_markdown(&self) -> String {
-        format!(r#"**Auto-Scaling Performance Test:** This test evaluated system auto-scaling effectiveness across {} concurrency levels, from {} to {} concurrent connections. Total of {} requests were processed across all scenarios.
-
-**Auto-Scaling Characteristics:**
-- **Scaling Effectiveness:** {} (Performance improved consistently as instances scaled)
-- **Peak Performance:** Achieved at {} concurrent connections (optimal auto-scaling)
-- **Scaling Threshold:** Auto-scaling activated around {} concurrent connections
-- **Infrastructure Efficiency:** {:.1}x throughput improvement through automatic instance provisioning"#,
-            self.scenarios.len(),
-            self.scenarios.first().map(|s| s.concurrency).unwrap_or(0),
-            self.scenarios.last().map(|s| s.concurrency).unwrap_or(0),
-            self.overall_requests,
-            if self.is_linear_scaling() { "Excellent" } else { "Needs Optimization" },
-            self.get_best_performing_concurrency(),
-            self.get_breaking_point(),
-            self.get_scalability_factor()
-        )
-    }
-
-    fn generate_scaling_table_markdown(&self) -> String {
-        let mut table = String::new();
-        
-        for scenario in &self.scenarios {
-            let performance_score = self.calculate_performance_score(scenario);
-            table.push_str(&format!(
-                "| {} | {} | {:.2} | {:.2} | {:.2} | {} | {:.2} | {:.1} |\n",
-                scenario.concurrency,
-                scenario.total_requests,
-                scenario.success_rate,
-                scenario.rps,
-                scenario.mean_latency,
-                scenario.p95_latency,
-                scenario.duration_seconds,
-                performance_score
-            ));
-        }
-        
-        table
-    }
-
     fn get_bottleneck_analysis(&self) -> String {
         if self.scenarios.len() < 2 {
             return "Insufficient data for bottleneck analysis".to_string();
@@ -852,29 +845,6 @@ Based on the scaling analysis:
         }
     }
 
-    fn generate_scaling_summary(&self) -> String {
-        format!(r#"
-        <p><strong>Scaling Test Summary:</strong> This test evaluated system performance across {} concurrency levels, 
-        from {} to {} concurrent connections. Total of {} requests were processed across all scenarios.</p>
-        
-        <h4>Performance Characteristics:</h4>
-        <ul>
-            <li><strong>Linear Scaling:</strong> {} (Performance improved consistently with load)</li>
-            <li><strong>Peak Performance:</strong> Achieved at {} concurrent connections</li>
-            <li><strong>Degradation Point:</strong> Performance issues start at {} concurrent connections</li>
-            <li><strong>Resource Efficiency:</strong> {:.1}x improvement from baseline to peak</li>
-        </ul>"#,
-            self.scenarios.len(),
-            self.scenarios.first().map(|s| s.concurrency).unwrap_or(0),
-            self.scenarios.last().map(|s| s.concurrency).unwrap_or(0),
-            self.overall_requests,
-            if self.is_linear_scaling() { "Yes" } else { "No" },
-            self.get_best_performing_concurrency(),
-            self.get_breaking_point(),
-            self.get_scalability_factor()
-        )
-    }
-
     fn get_best_performing_concurrency(&self) -> usize {
         self.scenarios.iter()
             .max_by(|a, b| {
@@ -905,9 +875,9 @@ Based on the scaling analysis:
             let previous = &self.scenarios[i-1];
             
             // Breaking point criteria for auto-scaling systems:
-            if current.success_rate < 95.0 || 
-               current.rps < previous.rps * 0.8 || // 20% RPS drop
-               current.mean_latency > previous.mean_latency * 1.5 { // 50% latency increase
+            if current.success_rate < SCALING_THRESHOLDS.min_success_rate ||
+               current.rps < previous.rps * SCALING_THRESHOLDS.min_rps_ratio ||
+               current.mean_latency > previous.mean_latency * SCALING_THRESHOLDS.max_latency_ratio {
                 return current.concurrency;
             }
         }
@@ -962,7 +932,7 @@ Based on the scaling analysis:
         increasing_count as f64 / (self.scenarios.len() - 1) as f64 >= 0.7 // 70% should be increasing
     }
 
-    fn calculate_performance_score(&self, scenario: &crate::models::ScenarioResult) -> f64 {
+    fn calculate_performance_score(&self, scenario: &ScenarioResult) -> f64 {
         // Performance score based on RPS, success rate, and latency
         let rps_score = scenario.rps / 100.0; // Normalize RPS
         let success_penalty = (100.0 - scenario.success_rate) / 10.0; // Penalty for failures
@@ -971,70 +941,116 @@ Based on the scaling analysis:
         (rps_score - success_penalty - latency_penalty).max(0.0)
     }
 
-    fn get_recommendations(&self) -> String {
-        let (optimal_start, optimal_end) = self.get_optimal_range();
-        let breaking_point = self.get_breaking_point();
-        let scalability_factor = self.get_scalability_factor();
-        
-        format!(r#"Based on the scaling analysis:
-
-1. **Optimal Operating Range:** {} - {} concurrent connections provide the best balance of throughput and latency
-2. **Performance Degradation:** Monitor closely beyond {} concurrent connections
-3. **Resource Planning:** System shows {:.1}x scaling capability from baseline to peak performance
-4. **Bottleneck Analysis:** {}"#,
-            optimal_start, optimal_end, breaking_point, scalability_factor, self.get_bottleneck_analysis())
-    }
-
-    fn generate_scaling_comparison_table(&self) -> String {
-        let mut rows = String::new();
-        
-        for scenario in &self.scenarios {
-            let performance_score = self.calculate_performance_score(scenario);
-            rows.push_str(&format!(r#"
-            <tr>
-                <td>{}</td>
-                <td>{}</td>
-                <td>{:.2}</td>
-                <td>{:.2}</td>
-                <td>{:.2}</td>
-                <td>{}</td>
-                <td>{}</td>
-                <td>{:.2}</td>
-                <td>{:.1}</td>
-            </tr>"#,
-                scenario.concurrency,
-                scenario.total_requests,
-                scenario.success_rate,
-                scenario.rps,
-                scenario.mean_latency,
-                scenario.p95_latency,
-                scenario.p99_latency,
-                scenario.duration_seconds,
-                performance_score
-            ));
-        }
-        
-        rows
-    }
 }
 
 pub fn save_report(report: &LoadTestReport) -> Result<(), Box<dyn std::error::Error>> {
+    save_report_with_options(report, &ReportOptions::default())
+}
+
+pub fn save_report_with_options(report: &LoadTestReport, options: &ReportOptions) -> Result<(), Box<dyn std::error::Error>> {
     // Save HTML report as index.html
     let mut html_file = File::create("index.html")?;
-    html_file.write_all(report.generate_html().as_bytes())?;
+    html_file.write_all(report.generate_html_with_options(options)?.as_bytes())?;
     
     // Save Markdown report as README.md
     let mut md_file = File::create("README.md")?;
-    md_file.write_all(report.generate_markdown().as_bytes())?;
+    md_file.write_all(report.generate_markdown_with_options(options)?.as_bytes())?;
     
     // Save JSON data for further analysis
     let mut json_file = File::create("load_test_data.json")?;
     json_file.write_all(serde_json::to_string_pretty(report)?.as_bytes())?;
+
+    // Save threshold results as JUnit XML for CI
+    let mut junit_file = File::create("junit.xml")?;
+    junit_file.write_all(report.generate_junit_xml(&options.thresholds).as_bytes())?;
+
+    // Save flat tables for notebooks and spreadsheets
+    save_csv_tables(report, Path::new(DEFAULT_CSV_DIR))?;
     
-    println!("\n📄 Reports generated:");
-    println!("   - index.html");
-    println!("   - README.md");
-    println!("   - load_test_data.json");
+    info!(
+        csv_dir = DEFAULT_CSV_DIR,
+        "Reports generated: index.html, README.md, load_test_data.json, junit.xml and CSV tables"
+    );
     
     Ok(())
 }
+
+fn chart_container(title: &str, svg: String) -> String {
+    format!(r#"
+        <div class="chart-container">
+            <h3>{}</h3>
+            {}
+        </div>
+"#, title, svg)
+}
+
+/// `text` as a JavaScript string literal, with `<` escaped so it can't close the `<script>` it sits in
+fn js_string(text: &str) -> String {
+    serde_json::to_string(text).unwrap_or_default().replace('<', "\\u003c")
+}
+
+fn load_template(path: Option<&PathBuf>, default: &str) -> Result<String, Box<dyn std::error::Error>> {
+    match path {
+        Some(path) => fs::read_to_string(path)
+            .map_err(|e| format!("failed to read template {}: {}", path.display(), e).into()),
+        None => Ok(default.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::fixtures::{endpoint, report, scenario};
+
+    fn two_scenarios() -> LoadTestReport {
+        report(vec![
+            scenario(10, vec![endpoint("/users", 100, 0, 50)]),
+            scenario(20, vec![endpoint("/users", 200, 4, 90)]),
+        ])
+    }
+
+    #[test]
+    fn embedded_charts_need_no_network() {
+        let html = two_scenarios().generate_html();
+        assert!(html.contains("<svg"));
+        assert!(!html.contains("cdn.jsdelivr.net"));
+    }
+
+    #[test]
+    fn cdn_charts_load_chart_js() {
+        let options = ReportOptions { chart_mode: ChartMode::Cdn, ..ReportOptions::default() };
+        let html = two_scenarios().generate_html_with_options(&options).unwrap();
+        assert!(html.contains(r#"<script src="https://cdn.jsdelivr.net/npm/chart.js"></script>"#));
+        assert!(html.contains("<canvas"));
+    }
+
+    #[test]
+    fn cdn_chart_labels_are_escaped_javascript_strings() {
+        // Cut mid-way through the multibyte names, which byte slicing would panic on
+        let long = format!("/é\"{}", "ü".repeat(40));
+        let endpoints = vec![endpoint(&long, 10, 0, 5), endpoint("/a</script><script>", 10, 0, 5)];
+        let options = ReportOptions { chart_mode: ChartMode::Cdn, ..ReportOptions::default() };
+        let html = report(vec![scenario(10, endpoints)]).generate_html_with_options(&options).unwrap();
+        let label: String = long.chars().take(30).collect();
+        assert!(html.contains(&serde_json::to_string(&label).unwrap()));
+        assert!(html.contains(r#""/a\u003c/script>\u003cscript>""#));
+        assert!(!html.contains("</script><script>"));
+    }
+
+    #[test]
+    fn cdn_charts_render_a_run_without_scenarios() {
+        let options = ReportOptions { chart_mode: ChartMode::Cdn, ..ReportOptions::default() };
+        assert!(report(Vec::new()).generate_html_with_options(&options).is_ok());
+    }
+
+    #[test]
+    fn partial_reports_say_how_many_entries_were_dropped() {
+        let mut report = two_scenarios();
+        assert!(!report.generate_markdown().contains("Partial report"));
+
+        report.dropped_log_entries = 1200;
+        let notice = "request log rotation dropped the oldest 1200 entries";
+        assert!(report.generate_markdown().contains(&format!("**Partial report: {}**", notice)));
+        assert!(report.generate_html().contains(notice));
+    }
+}
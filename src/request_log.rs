@@ -0,0 +1,537 @@
+use crate::load_tester::build_report;
+use crate::metrics::{HISTOGRAM_BINS, LatencyMetrics, PercentileWithConfidence, TimeSeriesRecorder};
+use crate::models::{EndpointResult, ErrorClass, LoadTestReport, ScenarioResult, SlowRequest};
+use crate::resolve::AddressRecorder;
+use crate::trace_context::{DEFAULT_SLOWEST_REQUESTS, keep_slowest};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::warn;
+
+pub const DEFAULT_REQUEST_LOG: &str = "requests.jsonl";
+
+/// Settings for the raw per-request log
+#[derive(Clone)]
+pub struct RequestLogConfig {
+    pub path: PathBuf,
+    pub success_sample_rate: f64,   // Fraction of successful requests written; errors are always written
+    pub max_file_bytes: u64,        // Rotate to `<path>.1`, `<path>.2`, ... once the active file passes this size
+    pub max_files: usize,           // Rotated files kept besides the active one
+    pub correlation_header: String, // Response header recorded as the correlation ID
+    pub slow_request_ms: Option<u64>, // Successes at least this slow are always written, like errors
+}
+
+impl Default for RequestLogConfig {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from(DEFAULT_REQUEST_LOG),
+            success_sample_rate: 1.0,
+            max_file_bytes: 100 * 1024 * 1024,
+            max_files: 5,
+            correlation_header: "x-request-id".to_string(),
+            slow_request_ms: None,
+        }
+    }
+}
+
+/// One line of `requests.jsonl`
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RequestLogEntry {
+    pub sent_at: DateTime<Local>,
+    pub scenario: usize, // 1-based scenario index
+    pub concurrency: usize,
+    pub endpoint: String,
+    pub url: String,
+    pub status: Option<u16>, // None when no response was received
+    pub headers_ms: u64,     // Send until response headers; the latency used in aggregates
+    pub body_ms: u64,        // Reading the response body
+    pub total_ms: u64,
+    pub bytes: u64,
+    pub in_flight: usize, // Requests in flight for the endpoint when this one completed
+    pub error_class: Option<ErrorClass>,
+    pub error: Option<String>,
+    pub correlation_id: Option<String>,
+    #[serde(default)]
+    pub trace_id: Option<String>, // From the `traceparent` header the tester sent
+    #[serde(default)]
+    pub request_id: Option<String>, // Request ID header the tester sent
+    #[serde(default)]
+    pub address: Option<IpAddr>, // Address connected to, when the host has a `resolve` override
+    #[serde(default)]
+    pub source_address: Option<IpAddr>, // Local address sent from, when source addresses are configured
+    #[serde(default)]
+    pub grpc_code: Option<u16>, // gRPC status code on gRPC rows; `status` stays the HTTP status
+    pub sample_rate: f64, // Probability this entry was written; it stands for 1/sample_rate requests
+    #[serde(default)]
+    pub sequence: u64, // Position among the entries written in the run; the oldest left after rotation shows how many were dropped
+}
+
+/// Appends sampled entries to the request log, rotating by size
+pub struct RequestLogWriter {
+    config: RequestLogConfig,
+    file: BufWriter<File>,
+    written: u64,
+    sequence: u64,
+    dropped: bool,
+}
+
+impl RequestLogWriter {
+    /// Start a fresh log, removing the active file and rotated files from an earlier run
+    pub fn create(config: RequestLogConfig) -> io::Result<Self> {
+        for index in 1.. {
+            let rotated = rotated_path(&config.path, index);
+            if !rotated.exists() {
+                break;
+            }
+            fs::remove_file(rotated)?;
+        }
+        let file = BufWriter::new(File::create(&config.path)?);
+        Ok(Self { config, file, written: 0, sequence: 0, dropped: false })
+    }
+
+    /// Sample rate that applies to a request, or `None` if it should not be written
+    pub fn sample(&self, is_error: bool, latency_ms: u64) -> Option<f64> {
+        if is_error || self.config.slow_request_ms.is_some_and(|slow| latency_ms >= slow) {
+            return Some(1.0);
+        }
+        let rate = self.config.success_sample_rate.clamp(0.0, 1.0);
+        (rate > 0.0 && rand::random::<f64>() < rate).then_some(rate)
+    }
+
+    pub fn write(&mut self, mut entry: RequestLogEntry) -> io::Result<()> {
+        entry.sequence = self.sequence;
+        self.sequence += 1;
+        let line = serde_json::to_string(&entry)?;
+        if self.written > 0 && self.written + line.len() as u64 + 1 > self.config.max_file_bytes {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.written += line.len() as u64 + 1;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.config.max_files == 0 || rotated_path(&self.config.path, self.config.max_files).exists() {
+            self.warn_dropped();
+        }
+        let path = &self.config.path;
+        if self.config.max_files == 0 {
+            self.file = BufWriter::new(File::create(path)?);
+            self.written = 0;
+            return Ok(());
+        }
+
+        let oldest = rotated_path(path, self.config.max_files);
+        if oldest.exists() {
+            fs::remove_file(oldest)?;
+        }
+        for index in (1..self.config.max_files).rev() {
+            let from = rotated_path(path, index);
+            if from.exists() {
+                fs::rename(from, rotated_path(path, index + 1))?;
+            }
+        }
+        fs::rename(path, rotated_path(path, 1))?;
+
+        self.file = BufWriter::new(File::create(path)?);
+        self.written = 0;
+        Ok(())
+    }
+
+    fn warn_dropped(&mut self) {
+        if !self.dropped {
+            self.dropped = true;
+            warn!(
+                path = %self.config.path.display(),
+                max_files = self.config.max_files,
+                "Request log rotation is dropping the oldest entries; reports rebuilt from it will be partial"
+            );
+        }
+    }
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+/// Read a request log and its rotated files, oldest entries first
+pub fn read_request_log(path: &Path) -> Result<Vec<RequestLogEntry>, Box<dyn std::error::Error>> {
+    let mut files = Vec::new();
+    for index in 1.. {
+        let rotated = rotated_path(path, index);
+        if !rotated.exists() {
+            break;
+        }
+        files.push(rotated);
+    }
+    files.reverse();
+    files.push(path.to_path_buf());
+
+    let mut entries = Vec::new();
+    for file in files {
+        for (number, line) in BufReader::new(File::open(&file)?).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = serde_json::from_str(&line)
+                .map_err(|e| format!("{}:{}: {}", file.display(), number + 1, e))?;
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+/// Rebuild the report aggregates from request log entries.
+///
+/// Sampled entries count `1/sample_rate` times in request counts and RPS, which are
+/// then estimates. Latencies are weighted the same way, so successes thinned to a low rate
+/// still outweigh the errors and slow requests that are always written; confidence intervals
+/// report the number of entries actually in the log as their sample size.
+/// When rotation dropped the oldest entries the report is marked partial.
+pub fn rebuild_report(entries: &[RequestLogEntry]) -> Result<LoadTestReport, Box<dyn std::error::Error>> {
+    let (Some(first), Some(last_sent)) = (
+        entries.iter().min_by_key(|entry| entry.sent_at),
+        entries.iter().map(|entry| entry.sent_at).max(),
+    ) else {
+        return Err("request log is empty".into());
+    };
+    let test_start_time = first.sent_at;
+    let test_end_time = entries.iter()
+        .map(completed_at)
+        .max()
+        .unwrap_or(last_sent);
+    let base_url = first.url.strip_suffix(first.endpoint.as_str()).unwrap_or(&first.url).to_string();
+
+    let mut scenario_numbers: Vec<usize> = entries.iter().map(|entry| entry.scenario).collect();
+    scenario_numbers.sort_unstable();
+    scenario_numbers.dedup();
+
+    let mut endpoints: Vec<String> = Vec::new();
+    let mut scenarios = Vec::new();
+    for number in scenario_numbers {
+        let scenario_entries: Vec<&RequestLogEntry> = entries.iter().filter(|entry| entry.scenario == number).collect();
+        for entry in &scenario_entries {
+            if !endpoints.contains(&entry.endpoint) {
+                endpoints.push(entry.endpoint.clone());
+            }
+        }
+        scenarios.push(rebuild_scenario(&scenario_entries, &endpoints));
+    }
+
+    let overall_duration = (test_end_time - test_start_time).to_std().unwrap_or_default();
+    let mut report = build_report(&base_url, test_start_time, overall_duration, scenarios, &endpoints);
+    report.test_end_time = test_end_time;
+    report.dropped_log_entries = entries.iter().map(|entry| entry.sequence).min().unwrap_or(0);
+    if report.dropped_log_entries > 0 {
+        warn!(
+            dropped = report.dropped_log_entries,
+            "Request log rotation dropped the oldest entries; the rebuilt report is partial"
+        );
+    }
+    Ok(report)
+}
+
+fn rebuild_scenario(entries: &[&RequestLogEntry], endpoints: &[String]) -> ScenarioResult {
+    let start = entries.iter().map(|entry| entry.sent_at).min().unwrap_or_else(Local::now);
+    let end = entries.iter().map(|entry| completed_at(entry)).max().unwrap_or(start);
+    let duration = (end - start).to_std().unwrap_or_default();
+
+    let mut latencies = LatencyMetrics::new();
+    let mut time_series = TimeSeriesRecorder::new(Duration::from_secs(1));
+    let mut total = 0;
+    let mut errors = 0;
+    for entry in entries {
+        let weight = weight(entry);
+        let is_error = entry.error_class.is_some();
+        total += weight;
+        if is_error {
+            errors += weight;
+        }
+        let offset = (completed_at(entry) - start).to_std().unwrap_or_default();
+        latencies.add_latency_times(entry.headers_ms, weight);
+        time_series.record_at_offset(offset, entry.headers_ms, is_error, entry.in_flight, weight);
+    }
+
+    let endpoint_results = endpoints.iter()
+        .filter_map(|endpoint| {
+            let endpoint_entries: Vec<&RequestLogEntry> = entries.iter()
+                .copied()
+                .filter(|entry| &entry.endpoint == endpoint)
+                .collect();
+            (!endpoint_entries.is_empty()).then(|| rebuild_endpoint(endpoint, &endpoint_entries))
+        })
+        .collect();
+
+    ScenarioResult {
+        concurrency: entries.first().map(|entry| entry.concurrency).unwrap_or(0),
+        total_requests: total,
+        successful_requests: total - errors,
+        failed_requests: errors,
+        success_rate: percentage(total - errors, total),
+        rps: per_second(total, duration),
+        mean_latency: latencies.mean(),
+        p95_latency: latencies.percentile(95.0),
+        p99_latency: latencies.percentile(99.0),
+        p95_confidence: confidence(&latencies, 95.0, entries.len()),
+        p99_confidence: confidence(&latencies, 99.0, entries.len()),
+        duration_seconds: duration.as_secs_f64(),
+        endpoints: endpoint_results,
+        time_series: time_series.buckets(),
+        percentile_spectrum: latencies.percentile_spectrum(),
+    }
+}
+
+fn rebuild_endpoint(endpoint: &str, entries: &[&RequestLogEntry]) -> EndpointResult {
+    let start = entries.iter().map(|entry| entry.sent_at).min().unwrap_or_else(Local::now);
+    let end = entries.iter().map(|entry| completed_at(entry)).max().unwrap_or(start);
+    let duration = (end - start).to_std().unwrap_or_default();
+
+    let mut latencies = LatencyMetrics::new();
+    let mut status_codes = HashMap::new();
+    let mut grpc_status_codes = HashMap::new();
+    let mut slowest_requests = Vec::new();
+    let mut address_results = AddressRecorder::default();
+    let mut source_results = AddressRecorder::default();
+    let mut total = 0;
+    let mut errors = 0;
+    for entry in entries {
+        let weight = weight(entry);
+        total += weight;
+        if entry.error_class.is_some() {
+            errors += weight;
+        }
+        *status_codes.entry(entry.status.unwrap_or(0)).or_insert(0) += weight;
+        if let Some(code) = entry.grpc_code {
+            *grpc_status_codes.entry(code).or_insert(0) += weight;
+        }
+        latencies.add_latency_times(entry.headers_ms, weight);
+        let slow_request = SlowRequest {
+            sent_at: entry.sent_at,
+            latency_ms: entry.headers_ms,
+            status: entry.status,
+            trace_id: entry.trace_id.clone(),
+            request_id: entry.request_id.clone(),
+        };
+        keep_slowest(&mut slowest_requests, slow_request, DEFAULT_SLOWEST_REQUESTS);
+        if let Some(address) = entry.address {
+            address_results.record(address, entry.headers_ms, entry.error_class.is_some(), weight);
+        }
+        if let Some(source) = entry.source_address {
+            source_results.record(source, entry.headers_ms, entry.error_class.is_some(), weight);
+        }
+    }
+
+    EndpointResult {
+        endpoint: endpoint.to_string(),
+        total_requests: total,
+        successful_requests: total - errors,
+        failed_requests: errors,
+        success_rate: percentage(total - errors, total),
+        rps: per_second(total, duration),
+        mean_latency: latencies.mean(),
+        p95_latency: latencies.percentile(95.0),
+        p99_latency: latencies.percentile(99.0),
+        p95_confidence: confidence(&latencies, 95.0, entries.len()),
+        p99_confidence: confidence(&latencies, 99.0, entries.len()),
+        duration_seconds: duration.as_secs_f64(),
+        status_codes,
+        grpc_status_codes,
+        slowest_requests,
+        latency_histogram: latencies.histogram(HISTOGRAM_BINS),
+        percentile_spectrum: latencies.percentile_spectrum(),
+        tls: None, // Not in the log
+        addresses: address_results.results(),
+        source_addresses: source_results.results(),
+        websocket: None,
+        event_stream: None,
+    }
+}
+
+fn completed_at(entry: &RequestLogEntry) -> DateTime<Local> {
+    entry.sent_at + chrono::Duration::milliseconds(entry.headers_ms as i64)
+}
+
+/// `percentile` of the weighted latencies, with the sample size set to the `entries` actually written
+fn confidence(latencies: &LatencyMetrics, percentile: f64, entries: usize) -> PercentileWithConfidence {
+    PercentileWithConfidence { sample_size: entries, ..latencies.percentile_with_confidence(percentile) }
+}
+
+fn weight(entry: &RequestLogEntry) -> usize {
+    if entry.sample_rate > 0.0 {
+        (1.0 / entry.sample_rate).round().max(1.0) as usize
+    } else {
+        1
+    }
+}
+
+fn percentage(part: usize, total: usize) -> f64 {
+    if total > 0 {
+        (part as f64 / total as f64) * 100.0
+    } else {
+        0.0
+    }
+}
+
+fn per_second(count: usize, duration: Duration) -> f64 {
+    if duration.as_secs_f64() > 0.0 {
+        count as f64 / duration.as_secs_f64()
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(offset_ms: i64, latency_ms: u64, status: u16, sample_rate: f64) -> RequestLogEntry {
+        let sent_at = DateTime::parse_from_rfc3339("2024-05-01T12:00:00+00:00").unwrap().with_timezone(&Local)
+            + chrono::Duration::milliseconds(offset_ms);
+        RequestLogEntry {
+            sent_at,
+            scenario: 1,
+            concurrency: 4,
+            endpoint: "/items".to_string(),
+            url: "http://localhost:8080/items".to_string(),
+            status: Some(status),
+            headers_ms: latency_ms,
+            body_ms: 1,
+            total_ms: latency_ms + 1,
+            bytes: 10,
+            in_flight: 1,
+            error_class: (status >= 500).then_some(ErrorClass::ServerError),
+            error: None,
+            correlation_id: None,
+            trace_id: None,
+            request_id: None,
+            address: None,
+            source_address: None,
+            grpc_code: None,
+            sample_rate,
+            sequence: 0,
+        }
+    }
+
+    fn temp_log(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("load-test-rs-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(DEFAULT_REQUEST_LOG)
+    }
+
+    #[test]
+    fn sampled_entries_weight_counts_and_latencies() {
+        let mut entries = vec![
+            entry(0, 100, 200, 0.1),
+            entry(500, 200, 200, 0.1),
+            entry(900, 50, 503, 1.0),
+        ];
+        for entry in &mut entries {
+            entry.address = Some("10.0.0.1".parse().unwrap());
+        }
+        let report = rebuild_report(&entries).unwrap();
+        let scenario = &report.scenarios[0];
+        assert_eq!(scenario.total_requests, 21);
+        assert_eq!(scenario.failed_requests, 1);
+        assert_eq!(scenario.p95_confidence.sample_size, 3);
+        assert_eq!(scenario.time_series[0].requests, 21);
+
+        let endpoint = &scenario.endpoints[0];
+        assert_eq!(endpoint.total_requests, 21);
+        assert_eq!(endpoint.status_codes[&200], 20);
+        assert_eq!(endpoint.status_codes[&503], 1);
+        assert_eq!(endpoint.p99_confidence.sample_size, 3);
+        assert_eq!(endpoint.p99_latency, 200);
+        assert_eq!((endpoint.addresses[0].total_requests, endpoint.addresses[0].failed_requests), (21, 1));
+        assert!((endpoint.addresses[0].mean_latency - 3050.0 / 21.0).abs() < 1e-9);
+        assert_eq!(report.dropped_log_entries, 0);
+    }
+
+    #[test]
+    fn thinned_successes_are_not_outweighed_by_errors_kept_at_full_rate() {
+        let mut entries: Vec<RequestLogEntry> = (0..5).map(|i| entry(i * 10, 10, 200, 0.01)).collect();
+        entries.extend((0..10).map(|i| entry(100 + i * 10, 1000, 503, 1.0)));
+        let report = rebuild_report(&entries).unwrap();
+        let scenario = &report.scenarios[0];
+        assert_eq!((scenario.total_requests, scenario.failed_requests), (510, 10));
+        assert_eq!(scenario.p95_latency, 10);
+        assert_eq!(scenario.p99_latency, 1000);
+        assert!((scenario.mean_latency - 15000.0 / 510.0).abs() < 1e-9);
+        assert_eq!(scenario.p95_confidence.value, 10);
+        assert_eq!(scenario.p95_confidence.sample_size, 15);
+
+        let endpoint = &scenario.endpoints[0];
+        assert_eq!(endpoint.p95_latency, 10);
+        assert_eq!(endpoint.p95_confidence.sample_size, 15);
+        assert_eq!(endpoint.latency_histogram.iter().map(|(_, count)| count).sum::<usize>(), 510);
+    }
+
+    #[test]
+    fn rebuild_marks_report_partial_when_oldest_entries_are_missing() {
+        let mut entries = vec![entry(0, 10, 200, 1.0), entry(10, 20, 200, 1.0)];
+        entries[0].sequence = 7;
+        entries[1].sequence = 8;
+        assert_eq!(rebuild_report(&entries).unwrap().dropped_log_entries, 7);
+    }
+
+    #[test]
+    fn rebuild_rejects_empty_log() {
+        assert!(rebuild_report(&[]).is_err());
+    }
+
+    #[test]
+    fn rotation_drops_oldest_files_and_numbers_entries() {
+        let path = temp_log("rotation");
+        let config = RequestLogConfig {
+            path: path.clone(),
+            max_file_bytes: 1,
+            max_files: 2,
+            ..Default::default()
+        };
+        let mut writer = RequestLogWriter::create(config).unwrap();
+        for i in 0..5 {
+            writer.write(entry(i * 10, 10, 200, 1.0)).unwrap();
+        }
+        writer.flush().unwrap();
+
+        // One entry per file: the active file and two rotated files survive
+        let entries = read_request_log(&path).unwrap();
+        assert_eq!(entries.iter().map(|entry| entry.sequence).collect::<Vec<_>>(), vec![2, 3, 4]);
+        assert_eq!(rebuild_report(&entries).unwrap().dropped_log_entries, 2);
+
+        // A new run starts from scratch
+        drop(writer);
+        let writer = RequestLogWriter::create(RequestLogConfig { path: path.clone(), ..Default::default() }).unwrap();
+        drop(writer);
+        assert!(!rotated_path(&path, 1).exists());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn sample_always_keeps_errors_and_slow_requests() {
+        let path = temp_log("sample");
+        let writer = RequestLogWriter::create(RequestLogConfig {
+            path: path.clone(),
+            success_sample_rate: 0.0,
+            slow_request_ms: Some(500),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(writer.sample(true, 1), Some(1.0));
+        assert_eq!(writer.sample(false, 600), Some(1.0));
+        assert_eq!(writer.sample(false, 10), None);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
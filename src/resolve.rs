@@ -0,0 +1,127 @@
+use crate::metrics::LatencyMetrics;
+use crate::models::AddressResult;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+
+/// How requests are spread over the addresses a host is resolved to
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AddressSelection {
+    #[default]
+    RoundRobin, // Request i goes to address i mod n, an even split
+    Random,     // Each request picks an address uniformly at random
+}
+
+impl AddressSelection {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "round-robin" | "round_robin" => Some(AddressSelection::RoundRobin),
+            "random" => Some(AddressSelection::Random),
+            _ => None,
+        }
+    }
+
+    /// Index of the address, out of `count`, used for the `request_index`-th request
+    pub(crate) fn pick(self, request_index: usize, count: usize) -> usize {
+        match self {
+            _ if count <= 1 => 0,
+            AddressSelection::RoundRobin => request_index % count,
+            AddressSelection::Random => rand::random::<usize>() % count,
+        }
+    }
+}
+
+/// Parse a `host=ip[,ip...]` resolve override, e.g. `api.example.com=10.0.0.1,10.0.0.2`
+pub fn parse_resolve(raw: &str) -> Option<(String, Vec<IpAddr>)> {
+    let (host, addresses) = raw.split_once('=')?;
+    let addresses: Vec<IpAddr> = addresses.split(',')
+        .map(|address| address.trim().trim_start_matches('[').trim_end_matches(']').parse())
+        .collect::<Result<_, _>>()
+        .ok()?;
+    let host = host.trim().to_ascii_lowercase();
+    (!host.is_empty() && !addresses.is_empty()).then_some((host, addresses))
+}
+
+/// Latencies and errors per target address for one endpoint
+#[derive(Default)]
+pub(crate) struct AddressRecorder {
+    addresses: BTreeMap<IpAddr, (LatencyMetrics, usize, usize)>, // (latencies, requests, errors)
+}
+
+impl AddressRecorder {
+    /// A sampled log entry stands for `weight` requests, in the counts and the latencies
+    pub(crate) fn record(&mut self, address: IpAddr, latency_ms: u64, is_error: bool, weight: usize) {
+        let (latencies, requests, errors) = self.addresses.entry(address).or_default();
+        latencies.add_latency_times(latency_ms, weight);
+        *requests += weight;
+        if is_error {
+            *errors += weight;
+        }
+    }
+
+    pub(crate) fn results(&self) -> Vec<AddressResult> {
+        self.addresses.iter()
+            .map(|(address, (latencies, requests, errors))| AddressResult {
+                address: *address,
+                total_requests: *requests,
+                failed_requests: *errors,
+                success_rate: if *requests > 0 {
+                    ((requests - errors) as f64 / *requests as f64) * 100.0
+                } else {
+                    0.0
+                },
+                mean_latency: latencies.mean(),
+                p95_latency: latencies.percentile(95.0),
+                p99_latency: latencies.percentile(99.0),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_resolve_accepts_ipv4_and_bracketed_ipv6() {
+        let (host, addresses) = parse_resolve(" API.Example.com =10.0.0.1, [::1]").unwrap();
+        assert_eq!(host, "api.example.com");
+        assert_eq!(addresses, vec!["10.0.0.1".parse::<IpAddr>().unwrap(), "::1".parse().unwrap()]);
+
+        assert!(parse_resolve("api.example.com").is_none());
+        assert!(parse_resolve("=10.0.0.1").is_none());
+        assert!(parse_resolve("api.example.com=").is_none());
+        assert!(parse_resolve("api.example.com=10.0.0.1,not-an-ip").is_none());
+    }
+
+    #[test]
+    fn selection_parses_and_picks_in_range() {
+        assert_eq!(AddressSelection::parse("round-robin"), Some(AddressSelection::RoundRobin));
+        assert_eq!(AddressSelection::parse("random"), Some(AddressSelection::Random));
+        assert_eq!(AddressSelection::parse("sticky"), None);
+
+        let picks: Vec<usize> = (0..6).map(|i| AddressSelection::RoundRobin.pick(i, 3)).collect();
+        assert_eq!(picks, vec![0, 1, 2, 0, 1, 2]);
+        assert!((0..100).all(|i| AddressSelection::Random.pick(i, 3) < 3));
+        assert_eq!(AddressSelection::Random.pick(5, 1), 0);
+        assert_eq!(AddressSelection::RoundRobin.pick(5, 0), 0);
+    }
+
+    #[test]
+    fn recorder_reports_each_address() {
+        let (first, second): (IpAddr, IpAddr) = ("10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap());
+        let mut recorder = AddressRecorder::default();
+        recorder.record(second, 30, true, 1);
+        recorder.record(first, 10, false, 1);
+        recorder.record(first, 20, false, 4);
+
+        let results = recorder.results();
+        assert_eq!(results.iter().map(|r| r.address).collect::<Vec<_>>(), vec![first, second]);
+        assert_eq!((results[0].total_requests, results[0].failed_requests), (5, 0));
+        assert!((results[0].mean_latency - 18.0).abs() < 1e-9);
+        assert!((results[0].success_rate - 100.0).abs() < 1e-9);
+        assert_eq!((results[1].total_requests, results[1].failed_requests, results[1].p95_latency), (1, 1, 30));
+        assert_eq!(results[1].success_rate, 0.0);
+    }
+}
@@ -0,0 +1,340 @@
+use crate::live::LiveMonitor;
+use chrono::{DateTime, Local};
+use reqwest::blocking::Client;
+use serde_json::json;
+use std::net::UdpSocket;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Aggregates for one endpoint at one concurrency over a push interval
+#[derive(Clone, Debug)]
+pub struct IntervalAggregate {
+    pub timestamp: DateTime<Local>, // End of the interval
+    pub interval_seconds: f64,
+    pub run_id: String,
+    pub concurrency: usize,
+    pub endpoint: String,
+    pub requests: usize,
+    pub errors: usize,
+    pub rps: f64,
+    pub mean_latency: f64,
+    pub p50_latency: u64,
+    pub p95_latency: u64,
+    pub p99_latency: u64,
+}
+
+/// Destination for per-interval aggregates; implement it to push somewhere new
+pub trait MetricsSink: Send {
+    fn name(&self) -> &str;
+    fn push(&mut self, aggregates: &[IntervalAggregate]) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+/// Built-in sinks, as configured in `LoadTestConfig::metrics_sinks`
+#[derive(Clone, Debug)]
+pub enum MetricsSinkConfig {
+    /// InfluxDB line protocol over HTTP, e.g. `http://localhost:8086/api/v2/write?org=o&bucket=b&precision=ms`
+    /// (the URL must select millisecond precision)
+    Influx { write_url: String, token: Option<String> },
+    /// StatsD over UDP; `dogstatsd` adds tags in the DogStatsD `|#tag:value` form
+    Statsd { addr: String, prefix: String, dogstatsd: bool },
+    /// OpenTelemetry collector via OTLP/HTTP JSON, e.g. `http://localhost:4318/v1/metrics`
+    Otlp { endpoint: String },
+}
+
+impl MetricsSinkConfig {
+    pub fn build(&self) -> Result<Box<dyn MetricsSink>, Box<dyn std::error::Error>> {
+        Ok(match self {
+            MetricsSinkConfig::Influx { write_url, token } => Box::new(InfluxSink {
+                client: Client::builder().timeout(Duration::from_secs(5)).build()?,
+                write_url: write_url.clone(),
+                token: token.clone(),
+            }),
+            MetricsSinkConfig::Statsd { addr, prefix, dogstatsd } => {
+                let socket = UdpSocket::bind("0.0.0.0:0")?;
+                socket.connect(addr)?;
+                Box::new(StatsdSink { socket, prefix: prefix.clone(), dogstatsd: *dogstatsd })
+            }
+            MetricsSinkConfig::Otlp { endpoint } => Box::new(OtlpSink {
+                client: Client::builder().timeout(Duration::from_secs(5)).build()?,
+                endpoint: endpoint.clone(),
+            }),
+        })
+    }
+}
+
+pub struct InfluxSink {
+    client: Client,
+    write_url: String,
+    token: Option<String>,
+}
+
+impl MetricsSink for InfluxSink {
+    fn name(&self) -> &str {
+        "influx"
+    }
+
+    fn push(&mut self, aggregates: &[IntervalAggregate]) -> Result<(), Box<dyn std::error::Error>> {
+        let body: Vec<String> = aggregates.iter().map(influx_line).collect();
+
+        let mut request = self.client.post(&self.write_url).body(body.join("\n"));
+        if let Some(token) = &self.token {
+            request = request.header("Authorization", format!("Token {}", token));
+        }
+        request.send()?.error_for_status()?;
+        Ok(())
+    }
+}
+
+pub struct StatsdSink {
+    socket: UdpSocket,
+    prefix: String,
+    dogstatsd: bool,
+}
+
+impl MetricsSink for StatsdSink {
+    fn name(&self) -> &str {
+        "statsd"
+    }
+
+    fn push(&mut self, aggregates: &[IntervalAggregate]) -> Result<(), Box<dyn std::error::Error>> {
+        for a in aggregates {
+            self.socket.send(statsd_lines(&self.prefix, self.dogstatsd, a).join("\n").as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// One InfluxDB line protocol point for `a`, timestamped in milliseconds
+fn influx_line(a: &IntervalAggregate) -> String {
+    format!(
+        "load_test,run_id={},concurrency={},endpoint={} requests={}i,errors={}i,rps={},mean_latency_ms={},p50_latency_ms={}i,p95_latency_ms={}i,p99_latency_ms={}i {}",
+        escape_influx_tag(&a.run_id),
+        a.concurrency,
+        escape_influx_tag(&a.endpoint),
+        a.requests,
+        a.errors,
+        a.rps,
+        a.mean_latency,
+        a.p50_latency,
+        a.p95_latency,
+        a.p99_latency,
+        a.timestamp.timestamp_millis()
+    )
+}
+
+/// StatsD lines for `a`, one per metric
+fn statsd_lines(prefix: &str, dogstatsd: bool, a: &IntervalAggregate) -> Vec<String> {
+    let prefix = escape_statsd_name(prefix);
+    let metrics = [
+        ("requests", a.requests.to_string(), "c"),
+        ("errors", a.errors.to_string(), "c"),
+        ("rps", format!("{:.2}", a.rps), "g"),
+        ("latency.mean", format!("{:.2}", a.mean_latency), "g"),
+        ("latency.p50", a.p50_latency.to_string(), "g"),
+        ("latency.p95", a.p95_latency.to_string(), "g"),
+        ("latency.p99", a.p99_latency.to_string(), "g"),
+    ];
+    metrics.iter()
+        .map(|(name, value, kind)| {
+            if dogstatsd {
+                format!(
+                    "{}.{}:{}|{}|#run_id:{},concurrency:{},endpoint:{}",
+                    prefix, name, value, kind, escape_dogstatsd_tag(&a.run_id), a.concurrency, escape_dogstatsd_tag(&a.endpoint)
+                )
+            } else {
+                // Plain StatsD has no tags; fold the run, concurrency and endpoint into the name
+                format!(
+                    "{}.{}.c{}.{}.{}:{}|{}",
+                    prefix, statsd_segment(&a.run_id), a.concurrency, statsd_segment(&a.endpoint), name, value, kind
+                )
+            }
+        })
+        .collect()
+}
+
+pub struct OtlpSink {
+    client: Client,
+    endpoint: String,
+}
+
+impl MetricsSink for OtlpSink {
+    fn name(&self) -> &str {
+        "otlp"
+    }
+
+    fn push(&mut self, aggregates: &[IntervalAggregate]) -> Result<(), Box<dyn std::error::Error>> {
+        let gauge = |name: &str, unit: &str, value: &dyn Fn(&IntervalAggregate) -> f64| {
+            json!({
+                "name": name,
+                "unit": unit,
+                "gauge": {
+                    "dataPoints": aggregates.iter().map(|a| json!({
+                        "timeUnixNano": a.timestamp.timestamp_nanos_opt().unwrap_or(0).to_string(),
+                        "asDouble": value(a),
+                        "attributes": [
+                            { "key": "run_id", "value": { "stringValue": a.run_id } },
+                            { "key": "concurrency", "value": { "intValue": a.concurrency.to_string() } },
+                            { "key": "endpoint", "value": { "stringValue": a.endpoint } },
+                        ],
+                    })).collect::<Vec<_>>(),
+                },
+            })
+        };
+
+        let body = json!({
+            "resourceMetrics": [{
+                "resource": {
+                    "attributes": [{ "key": "service.name", "value": { "stringValue": "load-test-rs" } }],
+                },
+                "scopeMetrics": [{
+                    "scope": { "name": "load-test-rs", "version": env!("CARGO_PKG_VERSION") },
+                    "metrics": [
+                        gauge("load_test.requests", "{request}", &|a| a.requests as f64),
+                        gauge("load_test.errors", "{request}", &|a| a.errors as f64),
+                        gauge("load_test.rps", "{request}/s", &|a| a.rps),
+                        gauge("load_test.latency.mean", "ms", &|a| a.mean_latency),
+                        gauge("load_test.latency.p50", "ms", &|a| a.p50_latency as f64),
+                        gauge("load_test.latency.p95", "ms", &|a| a.p95_latency as f64),
+                        gauge("load_test.latency.p99", "ms", &|a| a.p99_latency as f64),
+                    ],
+                }],
+            }],
+        });
+
+        self.client.post(&self.endpoint).json(&body).send()?.error_for_status()?;
+        Ok(())
+    }
+}
+
+fn escape_influx_tag(value: &str) -> String {
+    value.replace('\\', "\\\\").replace(',', "\\,").replace('=', "\\=").replace(' ', "\\ ")
+}
+
+/// DogStatsD tag value: `|`, `,` and `#` would end the tag or the line, so they and whitespace become `_`
+fn escape_dogstatsd_tag(value: &str) -> String {
+    value.chars()
+        .map(|c| if matches!(c, '|' | ',' | '#') || c.is_whitespace() || c.is_control() { '_' } else { c })
+        .collect()
+}
+
+/// StatsD metric name: `:`, `|` and `@` separate the value, type and sample rate, so they and whitespace become `_`
+fn escape_statsd_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if matches!(c, ':' | '|' | '@' | '#') || c.is_whitespace() || c.is_control() { '_' } else { c })
+        .collect()
+}
+
+/// One dot-free segment of a plain StatsD name; `_` for an empty value such as the `/` endpoint
+fn statsd_segment(endpoint: &str) -> String {
+    let segment: String = endpoint.trim_matches('/')
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    if segment.is_empty() { "_".to_string() } else { segment }
+}
+
+/// Background thread draining the monitor's interval aggregates into the sinks
+pub(crate) struct MetricsPusher {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+impl MetricsPusher {
+    pub(crate) fn start(
+        monitor: LiveMonitor,
+        sinks: Vec<Arc<Mutex<Box<dyn MetricsSink>>>>,
+        interval: Duration,
+        run_id: String,
+    ) -> Self {
+        monitor.enable_interval_aggregates();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+
+        let handle = thread::spawn(move || {
+            let mut interval_start = Instant::now();
+            loop {
+                let stopping = thread_stop.load(Ordering::SeqCst);
+                if !stopping && interval_start.elapsed() < interval {
+                    thread::sleep(Duration::from_millis(100));
+                    continue;
+                }
+
+                let interval_seconds = interval_start.elapsed().as_secs_f64();
+                interval_start = Instant::now();
+                let aggregates = monitor.take_interval_aggregates(&run_id, interval_seconds);
+                if !aggregates.is_empty() {
+                    for sink in &sinks {
+                        let mut sink = sink.lock().unwrap();
+                        if let Err(e) = sink.push(&aggregates) {
+                            warn!(sink = sink.name(), error = %e, "Error pushing metrics");
+                        }
+                    }
+                }
+
+                if stopping {
+                    break;
+                }
+            }
+        });
+
+        Self { stop, handle }
+    }
+
+    /// Push the last partial interval and wait for the thread to exit
+    pub(crate) fn finish(self) {
+        self.stop.store(true, Ordering::SeqCst);
+        let _ = self.handle.join();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aggregate(run_id: &str, endpoint: &str) -> IntervalAggregate {
+        IntervalAggregate {
+            timestamp: DateTime::parse_from_rfc3339("2024-05-01T12:00:00+00:00").unwrap().with_timezone(&Local),
+            interval_seconds: 10.0,
+            run_id: run_id.to_string(),
+            concurrency: 50,
+            endpoint: endpoint.to_string(),
+            requests: 120,
+            errors: 3,
+            rps: 12.0,
+            mean_latency: 41.5,
+            p50_latency: 38,
+            p95_latency: 90,
+            p99_latency: 140,
+        }
+    }
+
+    #[test]
+    fn influx_line_escapes_tags() {
+        let line = influx_line(&aggregate("nightly run,1", "/search?q=a b"));
+        assert_eq!(
+            line,
+            "load_test,run_id=nightly\\ run\\,1,concurrency=50,endpoint=/search?q\\=a\\ b \
+             requests=120i,errors=3i,rps=12,mean_latency_ms=41.5,p50_latency_ms=38i,p95_latency_ms=90i,p99_latency_ms=140i \
+             1714564800000"
+        );
+    }
+
+    #[test]
+    fn plain_statsd_folds_tags_into_the_name() {
+        let lines = statsd_lines("load test", false, &aggregate("run:1", "/users/{id}"));
+        assert_eq!(lines.len(), 7);
+        assert_eq!(lines[0], "load_test.run_1.c50.users__id_.requests:120|c");
+        assert_eq!(lines[3], "load_test.run_1.c50.users__id_.latency.mean:41.50|g");
+        assert_eq!(statsd_lines("lt", false, &aggregate("r", "/"))[0], "lt.r.c50._.requests:120|c");
+    }
+
+    #[test]
+    fn dogstatsd_tags_cannot_break_the_line() {
+        let lines = statsd_lines("lt", true, &aggregate("run|1,#x", "/a b\nc"));
+        assert_eq!(lines[1], "lt.errors:3|c|#run_id:run_1__x,concurrency:50,endpoint:/a_b_c");
+        assert!(lines.iter().all(|line| line.matches('|').count() == 2 && !line.contains('\n')));
+    }
+}
@@ -0,0 +1,356 @@
+use crate::http_client::{ConnectionClients, HttpClientConfig};
+use crate::models::ErrorClass;
+use reqwest::blocking::{Client, Response};
+use reqwest::header::{ACCEPT, CACHE_CONTROL, CONTENT_TYPE, COOKIE, HeaderValue};
+use std::io::{BufRead, BufReader};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Timeouts this close to the end of a subscription are its hold running out rather than a failure
+const HOLD_SLACK: Duration = Duration::from_millis(50);
+
+/// How an `EventStream` delivers events
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StreamMode {
+    ServerSentEvents, // One `text/event-stream` response held open; each dispatched event counts
+    LongPoll,         // GETs repeated back to back; each 2xx response with a body counts as an event
+}
+
+/// A streaming endpoint held open by many subscribers in every scenario, after the HTTP endpoints.
+///
+/// Each subscriber stays subscribed for `hold`; events are counted as they arrive and timed from
+/// the subscription (the first) or from the previous event (the rest).
+#[derive(Clone, Debug)]
+pub struct EventStream {
+    pub path: String, // Appended to the base URL
+    pub mode: StreamMode,
+    pub subscribers: Option<usize>, // Subscribed together; defaults to the scenario's concurrency
+    pub hold: Duration,             // How long each subscriber stays subscribed
+}
+
+impl EventStream {
+    pub fn new(path: &str, mode: StreamMode) -> Self {
+        Self {
+            path: path.to_string(),
+            mode,
+            subscribers: None,
+            hold: Duration::from_secs(30),
+        }
+    }
+
+    /// Name of the stream's row among the endpoint results, e.g. "SSE /notifications"
+    pub fn endpoint_name(&self) -> String {
+        match self.mode {
+            StreamMode::ServerSentEvents => format!("SSE {}", self.path),
+            StreamMode::LongPoll => format!("Long-poll {}", self.path),
+        }
+    }
+}
+
+/// What happened to a subscriber, reported as it happens
+pub(crate) enum StreamEvent {
+    Subscribed { latency_ms: u64, status: u16 }, // Response headers of the stream, or of the first poll
+    SubscribeFailed { latency_ms: u64, status: Option<u16>, error_class: ErrorClass, error: String },
+    Event { latency_ms: u64, first: bool }, // Since subscribing for the first event, else since the previous one
+    Disconnected { latency_ms: u64, error_class: ErrorClass, error: String }, // Ended before `hold`; latency since the last event
+}
+
+/// Subscribes to one `EventStream`, sharing its settings between subscriber threads.
+///
+/// Requests go through the endpoint's client settings, with the request timeout replaced by `hold`;
+/// skipping or aborting the scenario takes effect as subscriptions end.
+pub(crate) struct EventStreamClient<'a> {
+    stream: &'a EventStream,
+    clients: ConnectionClients,
+    url: String,
+    headers: Vec<(&'static str, String)>,
+}
+
+impl<'a> EventStreamClient<'a> {
+    pub(crate) fn new(
+        stream: &'a EventStream,
+        config: &HttpClientConfig,
+        base_url: &str,
+        headers: Vec<(&'static str, String)>,
+        subscribers: usize,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut config = config.clone();
+        config.request_timeout = stream.hold;
+        let shared = Arc::new(config.build_shared(base_url)?);
+        Ok(Self {
+            stream,
+            clients: ConnectionClients::new(&config, base_url, &shared, subscribers),
+            url: config.tls.request_url(&format!("{}{}", base_url, stream.path)),
+            headers,
+        })
+    }
+
+    /// Subscribe, count events until `hold` runs out and report each step to `on_event`
+    pub(crate) fn run_subscriber(&self, subscriber: usize, cookie: Option<HeaderValue>, on_event: &mut dyn FnMut(StreamEvent)) {
+        let client = match self.clients.client_for(subscriber) {
+            (Ok(client), _) => client,
+            (Err(error), _) => {
+                return on_event(StreamEvent::SubscribeFailed { latency_ms: 0, status: None, error_class: ErrorClass::Other, error });
+            }
+        };
+        let subscriber = Subscriber {
+            client: &client,
+            url: &self.url,
+            headers: &self.headers,
+            cookie,
+            started: Instant::now(),
+            deadline: Instant::now() + self.stream.hold,
+        };
+        match self.stream.mode {
+            StreamMode::ServerSentEvents => subscriber.read_event_stream(on_event),
+            StreamMode::LongPoll => subscriber.poll(on_event),
+        }
+    }
+}
+
+struct Subscriber<'a> {
+    client: &'a Client,
+    url: &'a str,
+    headers: &'a [(&'static str, String)],
+    cookie: Option<HeaderValue>,
+    started: Instant,
+    deadline: Instant,
+}
+
+impl Subscriber<'_> {
+    fn get(&self, accept: &'static str) -> reqwest::Result<Response> {
+        let mut request = self.client.get(self.url)
+            .header(ACCEPT, accept)
+            .header(CACHE_CONTROL, "no-cache")
+            .timeout(self.deadline.saturating_duration_since(Instant::now()));
+        for (name, value) in self.headers {
+            request = request.header(*name, value.as_str());
+        }
+        if let Some(cookie) = &self.cookie {
+            request = request.header(COOKIE, cookie.clone());
+        }
+        request.send()
+    }
+
+    fn held(&self) -> bool {
+        Instant::now() + HOLD_SLACK >= self.deadline
+    }
+
+    fn read_event_stream(&self, on_event: &mut dyn FnMut(StreamEvent)) {
+        let response = match self.get("text/event-stream") {
+            Ok(response) => response,
+            Err(e) => return on_event(subscribe_failed(self.started, &e)),
+        };
+        let status = response.status();
+        let latency_ms = self.started.elapsed().as_millis() as u64;
+        let content_type = response.headers().get(CONTENT_TYPE).and_then(|value| value.to_str().ok()).unwrap_or("");
+        if !status.is_success() || !content_type.starts_with("text/event-stream") {
+            let (error_class, error) = match ErrorClass::from_status(status.as_u16()) {
+                Some(error_class) => (error_class, format!("HTTP {}", status)),
+                None => (ErrorClass::Other, format!("not an event stream: content-type {:?}", content_type)),
+            };
+            return on_event(StreamEvent::SubscribeFailed { latency_ms, status: Some(status.as_u16()), error_class, error });
+        }
+        on_event(StreamEvent::Subscribed { latency_ms, status: status.as_u16() });
+
+        // An event is dispatched at the blank line ending it, if it carried data; comments are keep-alives
+        let mut reader = BufReader::new(response);
+        let mut line = String::new();
+        let mut has_data = false;
+        let mut last_event = self.started;
+        let mut first = true;
+        loop {
+            line.clear();
+            match reader.read_line(&mut line) {
+                Ok(0) => {
+                    if !self.held() {
+                        let latency_ms = last_event.elapsed().as_millis() as u64;
+                        on_event(StreamEvent::Disconnected { latency_ms, error_class: ErrorClass::Other, error: "closed by server".to_string() });
+                    }
+                    return;
+                }
+                Ok(_) => {
+                    let field = line.trim_end_matches(['\r', '\n']);
+                    if field.is_empty() {
+                        if has_data {
+                            on_event(StreamEvent::Event { latency_ms: last_event.elapsed().as_millis() as u64, first });
+                            last_event = Instant::now();
+                            first = false;
+                        }
+                        has_data = false;
+                    } else if field == "data" || field.starts_with("data:") {
+                        has_data = true;
+                    }
+                }
+                Err(_) if self.held() => return,
+                Err(e) => {
+                    let latency_ms = last_event.elapsed().as_millis() as u64;
+                    let error_class = if e.kind() == std::io::ErrorKind::TimedOut { ErrorClass::Timeout } else { ErrorClass::Other };
+                    return on_event(StreamEvent::Disconnected { latency_ms, error_class, error: e.to_string() });
+                }
+            }
+        }
+    }
+
+    fn poll(&self, on_event: &mut dyn FnMut(StreamEvent)) {
+        let mut last_event = self.started;
+        let mut subscribed = false;
+        let mut first = true;
+        while !self.held() {
+            let response = match self.get("*/*") {
+                Ok(response) => response,
+                Err(e) if e.is_timeout() && self.held() => return,
+                Err(e) if !subscribed => return on_event(subscribe_failed(self.started, &e)),
+                Err(e) => {
+                    let latency_ms = last_event.elapsed().as_millis() as u64;
+                    let error_class = if e.is_timeout() { ErrorClass::Timeout } else { ErrorClass::Other };
+                    return on_event(StreamEvent::Disconnected { latency_ms, error_class, error: e.to_string() });
+                }
+            };
+            let status = response.status();
+            if let Some(error_class) = ErrorClass::from_status(status.as_u16()) {
+                let error = format!("HTTP {}", status);
+                return if subscribed {
+                    on_event(StreamEvent::Disconnected { latency_ms: last_event.elapsed().as_millis() as u64, error_class, error })
+                } else {
+                    let latency_ms = self.started.elapsed().as_millis() as u64;
+                    on_event(StreamEvent::SubscribeFailed { latency_ms, status: Some(status.as_u16()), error_class, error })
+                };
+            }
+            if !subscribed {
+                subscribed = true;
+                on_event(StreamEvent::Subscribed { latency_ms: self.started.elapsed().as_millis() as u64, status: status.as_u16() });
+            }
+            match response.bytes() {
+                Ok(body) if !body.is_empty() => {
+                    on_event(StreamEvent::Event { latency_ms: last_event.elapsed().as_millis() as u64, first });
+                    last_event = Instant::now();
+                    first = false;
+                }
+                Ok(_) => {}
+                Err(_) if self.held() => return,
+                Err(e) => {
+                    let latency_ms = last_event.elapsed().as_millis() as u64;
+                    return on_event(StreamEvent::Disconnected { latency_ms, error_class: ErrorClass::Other, error: e.to_string() });
+                }
+            }
+        }
+    }
+}
+
+fn subscribe_failed(started: Instant, e: &reqwest::Error) -> StreamEvent {
+    let error_class = if e.is_timeout() {
+        ErrorClass::Timeout
+    } else if e.is_connect() {
+        ErrorClass::Connect
+    } else {
+        ErrorClass::Other
+    };
+    StreamEvent::SubscribeFailed { latency_ms: started.elapsed().as_millis() as u64, status: None, error_class, error: e.to_string() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread::{self, JoinHandle};
+
+    /// Answer one connection per response, in turn, then close it
+    fn serve(responses: Vec<&'static str>) -> (String, JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                    let read = stream.read(&mut buffer).unwrap();
+                    if read == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buffer[..read]);
+                }
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (base_url, handle)
+    }
+
+    fn run(stream: &EventStream, base_url: &str) -> Vec<StreamEvent> {
+        let client = EventStreamClient::new(stream, &HttpClientConfig::default(), base_url, Vec::new(), 1).unwrap();
+        let mut events = Vec::new();
+        client.run_subscriber(0, None, &mut |event| events.push(event));
+        events
+    }
+
+    #[test]
+    fn endpoint_names_follow_the_mode() {
+        assert_eq!(EventStream::new("/feed", StreamMode::ServerSentEvents).endpoint_name(), "SSE /feed");
+        assert_eq!(EventStream::new("/feed", StreamMode::LongPoll).endpoint_name(), "Long-poll /feed");
+    }
+
+    #[test]
+    fn sse_counts_events_with_data_and_reports_an_early_close() {
+        let (base_url, server) = serve(vec![
+            "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n\
+             : keep-alive\n\ndata: one\n\nevent: ping\n\ndata: two\r\nid: 2\r\n\r\n",
+        ]);
+        let mut stream = EventStream::new("/feed", StreamMode::ServerSentEvents);
+        stream.hold = Duration::from_secs(5);
+        let events = run(&stream, &base_url);
+        server.join().unwrap();
+
+        assert_eq!(events.len(), 4);
+        assert!(matches!(events[0], StreamEvent::Subscribed { status: 200, .. }));
+        assert!(matches!(events[1], StreamEvent::Event { first: true, .. }));
+        assert!(matches!(events[2], StreamEvent::Event { first: false, .. }));
+        assert!(matches!(
+            &events[3],
+            StreamEvent::Disconnected { error_class: ErrorClass::Other, error, .. } if error == "closed by server"
+        ));
+    }
+
+    #[test]
+    fn sse_subscription_fails_on_errors_and_other_content_types() {
+        let (base_url, server) = serve(vec![
+            "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}",
+        ]);
+        let stream = EventStream::new("/feed", StreamMode::ServerSentEvents);
+        let unavailable = run(&stream, &base_url);
+        let json = run(&stream, &base_url);
+        server.join().unwrap();
+
+        assert!(matches!(
+            unavailable[..],
+            [StreamEvent::SubscribeFailed { status: Some(503), error_class: ErrorClass::ServerError, .. }]
+        ));
+        assert!(matches!(
+            &json[..],
+            [StreamEvent::SubscribeFailed { status: Some(200), error_class: ErrorClass::Other, error, .. }]
+                if error.starts_with("not an event stream")
+        ));
+    }
+
+    #[test]
+    fn long_poll_counts_non_empty_responses_until_an_error() {
+        let (base_url, server) = serve(vec![
+            "HTTP/1.1 200 OK\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello",
+            "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Length: 5\r\nConnection: close\r\n\r\nworld",
+            "HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        ]);
+        let mut stream = EventStream::new("/poll", StreamMode::LongPoll);
+        stream.hold = Duration::from_secs(5);
+        let events = run(&stream, &base_url);
+        server.join().unwrap();
+
+        assert_eq!(events.len(), 4);
+        assert!(matches!(events[0], StreamEvent::Subscribed { status: 200, .. }));
+        assert!(matches!(events[1], StreamEvent::Event { first: true, .. }));
+        assert!(matches!(events[2], StreamEvent::Event { first: false, .. }));
+        assert!(matches!(events[3], StreamEvent::Disconnected { error_class: ErrorClass::ServerError, .. }));
+    }
+}
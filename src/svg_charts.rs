@@ -0,0 +1,439 @@
+//! Minimal server-side SVG charts so HTML reports render without any scripts
+//! or network access.
+
+const WIDTH: f64 = 800.0;
+const HEIGHT: f64 = 400.0;
+const MARGIN_LEFT: f64 = 70.0;
+const MARGIN_RIGHT: f64 = 70.0;
+const MARGIN_TOP: f64 = 40.0;
+const MARGIN_BOTTOM: f64 = 90.0;
+const Y_TICKS: usize = 5;
+
+pub const PRIMARY: &str = "#3498db";
+pub const SUCCESS: &str = "#2ecc71";
+pub const WARNING: &str = "#f39c12";
+pub const DANGER: &str = "#e74c3c";
+pub const INFO: &str = "#9b59b6";
+pub const SECONDARY: &str = "#95a5a6";
+
+/// One line of a line chart; `None` values leave a gap
+pub struct Series {
+    pub label: String,
+    pub values: Vec<Option<f64>>,
+    pub color: &'static str,
+}
+
+impl Series {
+    pub fn new(label: &str, values: Vec<f64>, color: &'static str) -> Self {
+        Series {
+            label: label.to_string(),
+            values: values.into_iter().map(Some).collect(),
+            color,
+        }
+    }
+}
+
+/// Escape text for HTML or SVG content and attribute values
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Smallest 1/2/2.5/5 x 10^k value at or above `max`
+fn nice_max(max: f64) -> f64 {
+    if max <= 0.0 || !max.is_finite() {
+        return 1.0;
+    }
+    let magnitude = 10f64.powf(max.log10().floor());
+    [1.0, 2.0, 2.5, 5.0, 10.0].iter()
+        .map(|step| step * magnitude)
+        .find(|candidate| *candidate >= max)
+        .unwrap_or(10.0 * magnitude)
+}
+
+fn format_tick(value: f64) -> String {
+    if value.fract().abs() < 1e-9 {
+        format!("{}", value as i64)
+    } else {
+        format!("{:.1}", value)
+    }
+}
+
+fn plot_width() -> f64 {
+    WIDTH - MARGIN_LEFT - MARGIN_RIGHT
+}
+
+fn plot_height() -> f64 {
+    HEIGHT - MARGIN_TOP - MARGIN_BOTTOM
+}
+
+fn y_position(value: f64, max: f64) -> f64 {
+    MARGIN_TOP + plot_height() - (value / max).clamp(0.0, 1.0) * plot_height()
+}
+
+/// Center of category `index` out of `count` along the x axis
+fn band_center(index: usize, count: usize) -> f64 {
+    MARGIN_LEFT + (index as f64 + 0.5) * plot_width() / count.max(1) as f64
+}
+
+fn open_svg(title: &str) -> String {
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {w} {h}" width="100%" style="max-width: {w}px; font-family: Arial, sans-serif; font-size: 11px;">
+    <text x="{cx}" y="16" text-anchor="middle" font-size="14" font-weight="bold">{title}</text>
+"#,
+        w = WIDTH,
+        h = HEIGHT,
+        cx = WIDTH / 2.0,
+        title = escape(title)
+    )
+}
+
+/// Gridlines, y tick labels on the left (or right) edge and the axis title
+fn y_axis(max: f64, title: &str, right: bool) -> String {
+    let mut svg = String::new();
+    let x = if right { WIDTH - MARGIN_RIGHT } else { MARGIN_LEFT };
+    for tick in 0..=Y_TICKS {
+        let value = max * tick as f64 / Y_TICKS as f64;
+        let y = y_position(value, max);
+        if !right {
+            svg.push_str(&format!(
+                "    <line x1=\"{}\" y1=\"{y:.1}\" x2=\"{}\" y2=\"{y:.1}\" stroke=\"#e0e0e0\"/>\n",
+                MARGIN_LEFT,
+                WIDTH - MARGIN_RIGHT
+            ));
+        }
+        svg.push_str(&format!(
+            "    <text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"{}\">{}</text>\n",
+            if right { x + 6.0 } else { x - 6.0 },
+            y + 4.0,
+            if right { "start" } else { "end" },
+            format_tick(value)
+        ));
+    }
+    let title_x = if right { WIDTH - 15.0 } else { 15.0 };
+    let title_y = MARGIN_TOP + plot_height() / 2.0;
+    svg.push_str(&format!(
+        "    <text x=\"{tx}\" y=\"{ty}\" text-anchor=\"middle\" transform=\"rotate(-90 {tx} {ty})\">{}</text>\n",
+        escape(title),
+        tx = title_x,
+        ty = title_y
+    ));
+    svg
+}
+
+fn x_axis(labels: &[String], title: &str, rotate: bool) -> String {
+    let mut svg = format!(
+        "    <line x1=\"{}\" y1=\"{y}\" x2=\"{}\" y2=\"{y}\" stroke=\"#333\"/>\n",
+        MARGIN_LEFT,
+        WIDTH - MARGIN_RIGHT,
+        y = MARGIN_TOP + plot_height()
+    );
+    // Thin out labels so they don't overlap on long series
+    let step = (labels.len() / 20).max(1);
+    for (index, label) in labels.iter().enumerate().filter(|(i, _)| i % step == 0) {
+        let x = band_center(index, labels.len());
+        let y = MARGIN_TOP + plot_height() + 15.0;
+        if rotate {
+            svg.push_str(&format!(
+                "    <text x=\"{x:.1}\" y=\"{y:.1}\" text-anchor=\"end\" transform=\"rotate(-45 {x:.1} {y:.1})\">{}</text>\n",
+                escape(label)
+            ));
+        } else {
+            svg.push_str(&format!("    <text x=\"{x:.1}\" y=\"{y:.1}\" text-anchor=\"middle\">{}</text>\n", escape(label)));
+        }
+    }
+    svg.push_str(&format!(
+        "    <text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n",
+        MARGIN_LEFT + plot_width() / 2.0,
+        HEIGHT - 8.0,
+        escape(title)
+    ));
+    svg
+}
+
+fn legend(entries: &[(&str, &str)]) -> String {
+    let mut svg = String::new();
+    let mut x = MARGIN_LEFT;
+    for (label, color) in entries {
+        svg.push_str(&format!(
+            "    <rect x=\"{x:.1}\" y=\"28\" width=\"12\" height=\"8\" fill=\"{color}\"/>\n    <text x=\"{:.1}\" y=\"36\">{}</text>\n",
+            x + 16.0,
+            escape(label)
+        ));
+        x += 28.0 + label.len() as f64 * 6.0;
+    }
+    svg
+}
+
+fn polyline(series: &Series, count: usize, max: f64) -> String {
+    let mut svg = String::new();
+    let mut segment: Vec<String> = Vec::new();
+    let flush = |segment: &mut Vec<String>, svg: &mut String| {
+        if segment.len() > 1 {
+            svg.push_str(&format!(
+                "    <polyline fill=\"none\" stroke=\"{}\" stroke-width=\"2\" points=\"{}\"/>\n",
+                series.color,
+                segment.join(" ")
+            ));
+        }
+        segment.clear();
+    };
+
+    for (index, value) in series.values.iter().enumerate() {
+        match value {
+            Some(value) => {
+                let (x, y) = (band_center(index, count), y_position(*value, max));
+                segment.push(format!("{:.1},{:.1}", x, y));
+                svg.push_str(&format!(
+                    "    <circle cx=\"{x:.1}\" cy=\"{y:.1}\" r=\"3\" fill=\"{}\"><title>{}: {}</title></circle>\n",
+                    series.color,
+                    escape(&series.label),
+                    format_tick(*value)
+                ));
+            }
+            None => flush(&mut segment, &mut svg),
+        }
+    }
+    flush(&mut segment, &mut svg);
+    svg
+}
+
+fn series_max(series: &[Series]) -> f64 {
+    series.iter()
+        .flat_map(|s| s.values.iter().flatten())
+        .fold(0.0, |max: f64, value| max.max(*value))
+}
+
+/// Line chart over categorical x labels, with optional series on a right axis
+pub fn line_chart(
+    title: &str,
+    x_labels: &[String],
+    x_title: &str,
+    left: (&str, &[Series]),
+    right: Option<(&str, &[Series])>,
+) -> String {
+    let mut svg = open_svg(title);
+    let left_max = nice_max(series_max(left.1));
+    svg.push_str(&y_axis(left_max, left.0, false));
+
+    let mut entries: Vec<(&str, &str)> = left.1.iter().map(|s| (s.label.as_str(), s.color)).collect();
+    for series in left.1 {
+        svg.push_str(&polyline(series, x_labels.len(), left_max));
+    }
+    if let Some((right_title, right_series)) = right {
+        let right_max = nice_max(series_max(right_series));
+        svg.push_str(&y_axis(right_max, right_title, true));
+        for series in right_series {
+            svg.push_str(&polyline(series, x_labels.len(), right_max));
+        }
+        entries.extend(right_series.iter().map(|s| (s.label.as_str(), s.color)));
+    }
+
+    svg.push_str(&x_axis(x_labels, x_title, false));
+    svg.push_str(&legend(&entries));
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Vertical bar chart, one bar per label
+pub fn bar_chart(title: &str, labels: &[String], values: &[f64], color: &str, x_title: &str, y_title: &str) -> String {
+    let mut svg = open_svg(title);
+    let max = nice_max(values.iter().cloned().fold(0.0, f64::max));
+    svg.push_str(&y_axis(max, y_title, false));
+
+    let band = plot_width() / labels.len().max(1) as f64;
+    for (index, (label, value)) in labels.iter().zip(values).enumerate() {
+        let y = y_position(*value, max);
+        svg.push_str(&format!(
+            "    <rect x=\"{:.1}\" y=\"{y:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{color}\"><title>{}: {}</title></rect>\n",
+            band_center(index, labels.len()) - band * 0.35,
+            band * 0.7,
+            MARGIN_TOP + plot_height() - y,
+            escape(label),
+            format_tick(*value)
+        ));
+    }
+
+    let rotate = labels.iter().any(|label| label.len() > 8);
+    svg.push_str(&x_axis(labels, x_title, rotate));
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Scatter plot of labelled (x, y) points on linear axes
+pub fn scatter_chart(title: &str, points: &[(f64, f64, String)], x_title: &str, y_title: &str) -> String {
+    let mut svg = open_svg(title);
+    let x_max = nice_max(points.iter().map(|p| p.0).fold(0.0, f64::max));
+    let y_max = nice_max(points.iter().map(|p| p.1).fold(0.0, f64::max));
+    svg.push_str(&y_axis(y_max, y_title, false));
+
+    let baseline = MARGIN_TOP + plot_height();
+    svg.push_str(&format!(
+        "    <line x1=\"{}\" y1=\"{baseline}\" x2=\"{}\" y2=\"{baseline}\" stroke=\"#333\"/>\n",
+        MARGIN_LEFT,
+        WIDTH - MARGIN_RIGHT
+    ));
+    for tick in 0..=Y_TICKS {
+        let value = x_max * tick as f64 / Y_TICKS as f64;
+        svg.push_str(&format!(
+            "    <text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>\n",
+            MARGIN_LEFT + value / x_max * plot_width(),
+            baseline + 15.0,
+            format_tick(value)
+        ));
+    }
+    svg.push_str(&format!(
+        "    <text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n",
+        MARGIN_LEFT + plot_width() / 2.0,
+        HEIGHT - 8.0,
+        escape(x_title)
+    ));
+
+    for (x, y, label) in points {
+        let cx = MARGIN_LEFT + (x / x_max).clamp(0.0, 1.0) * plot_width();
+        let cy = y_position(*y, y_max);
+        svg.push_str(&format!(
+            "    <circle cx=\"{cx:.1}\" cy=\"{cy:.1}\" r=\"6\" fill=\"{PRIMARY}\"><title>{}: {:.2}, {:.2}</title></circle>\n    <text x=\"{:.1}\" y=\"{:.1}\">{}</text>\n",
+            escape(label),
+            x,
+            y,
+            cx + 8.0,
+            cy - 8.0,
+            escape(label)
+        ));
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Doughnut chart of labelled slices
+pub fn doughnut_chart(title: &str, slices: &[(&str, f64, &str)]) -> String {
+    let mut svg = open_svg(title);
+    let total: f64 = slices.iter().map(|s| s.1).sum();
+    let (cx, cy, radius) = (WIDTH / 2.0, MARGIN_TOP + plot_height() / 2.0 + 20.0, 120.0);
+    let circumference = 2.0 * std::f64::consts::PI * radius;
+
+    // Each slice is a dashed circle stroke offset by the slices before it
+    let mut offset = 0.0;
+    for (label, value, color) in slices {
+        let share = if total > 0.0 { value / total } else { 0.0 };
+        let length = share * circumference;
+        svg.push_str(&format!(
+            "    <circle cx=\"{cx}\" cy=\"{cy}\" r=\"{radius}\" fill=\"none\" stroke=\"{color}\" stroke-width=\"50\" stroke-dasharray=\"{length:.2} {:.2}\" stroke-dashoffset=\"{:.2}\" transform=\"rotate(-90 {cx} {cy})\"><title>{}: {} ({:.2}%)</title></circle>\n",
+            circumference - length,
+            -offset,
+            escape(label),
+            format_tick(*value),
+            share * 100.0
+        ));
+        offset += length;
+    }
+
+    let entries: Vec<(&str, &str)> = slices.iter().map(|s| (s.0, s.2)).collect();
+    svg.push_str(&legend(&entries));
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Heatmap of counts: `rows[y][x]`, with row 0 drawn at the bottom. Cell shading
+/// is log-scaled so a handful of outliers still shows next to the bulk.
+pub fn heatmap_chart(
+    title: &str,
+    x_labels: &[String],
+    y_labels: &[String],
+    rows: &[Vec<usize>],
+    x_title: &str,
+    y_title: &str,
+) -> String {
+    let mut svg = open_svg(title);
+    let max = rows.iter().flatten().copied().max().unwrap_or(0);
+    let cell_width = plot_width() / x_labels.len().max(1) as f64;
+    let cell_height = plot_height() / y_labels.len().max(1) as f64;
+
+    for (row_index, row) in rows.iter().enumerate() {
+        let y = MARGIN_TOP + plot_height() - (row_index + 1) as f64 * cell_height;
+        svg.push_str(&format!(
+            "    <text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\">{}</text>\n",
+            MARGIN_LEFT - 6.0,
+            y + cell_height / 2.0 + 4.0,
+            escape(y_labels.get(row_index).map(String::as_str).unwrap_or(""))
+        ));
+        for (column, &count) in row.iter().enumerate().filter(|(_, count)| **count > 0) {
+            let intensity = (count as f64).ln_1p() / (max as f64).ln_1p();
+            svg.push_str(&format!(
+                "    <rect x=\"{:.1}\" y=\"{y:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{DANGER}\" fill-opacity=\"{:.2}\"><title>{} / {}: {}</title></rect>\n",
+                MARGIN_LEFT + column as f64 * cell_width,
+                cell_width,
+                cell_height,
+                0.1 + 0.9 * intensity,
+                escape(x_labels.get(column).map(String::as_str).unwrap_or("")),
+                escape(y_labels.get(row_index).map(String::as_str).unwrap_or("")),
+                count
+            ));
+        }
+    }
+
+    let title_y = MARGIN_TOP + plot_height() / 2.0;
+    svg.push_str(&format!(
+        "    <text x=\"15\" y=\"{title_y}\" text-anchor=\"middle\" transform=\"rotate(-90 15 {title_y})\">{}</text>\n",
+        escape(y_title)
+    ));
+    svg.push_str(&x_axis(x_labels, x_title, false));
+    svg.push_str("</svg>");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_covers_markup_characters() {
+        assert_eq!(escape(r#"<a href="x">&</a>"#), "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;");
+    }
+
+    #[test]
+    fn nice_max_rounds_up_to_a_readable_bound() {
+        assert_eq!(nice_max(0.0), 1.0);
+        assert_eq!(nice_max(f64::NAN), 1.0);
+        assert_eq!(nice_max(7.0), 10.0);
+        assert_eq!(nice_max(120.0), 200.0);
+        assert_eq!(nice_max(210.0), 250.0);
+        assert_eq!(nice_max(500.0), 500.0);
+    }
+
+    #[test]
+    fn format_tick_drops_whole_number_decimals() {
+        assert_eq!(format_tick(40.0), "40");
+        assert_eq!(format_tick(2.5), "2.5");
+    }
+
+    #[test]
+    fn line_chart_breaks_lines_at_gaps() {
+        let series = Series {
+            label: "p95".to_string(),
+            values: vec![Some(1.0), Some(2.0), None, Some(3.0), Some(4.0), None, Some(5.0)],
+            color: PRIMARY,
+        };
+        let labels: Vec<String> = (0..7).map(|i| i.to_string()).collect();
+        let svg = line_chart("Latency", &labels, "Second", ("ms", &[series]), None);
+        assert!(svg.starts_with("<svg") && svg.ends_with("</svg>"));
+        // Two segments of two points each; the lone trailing point is only a marker
+        assert_eq!(svg.matches("<polyline").count(), 2);
+        assert_eq!(svg.matches("<circle").count(), 5);
+    }
+
+    #[test]
+    fn bar_chart_escapes_labels_and_title() {
+        let svg = bar_chart(
+            "A & B",
+            &["<GET />".to_string()],
+            &[3.0],
+            SUCCESS,
+            "Endpoint",
+            "Requests",
+        );
+        assert!(svg.contains("A &amp; B"));
+        assert!(svg.contains("&lt;GET /&gt;: 3"));
+        assert!(!svg.contains("<GET"));
+    }
+}
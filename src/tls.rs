@@ -0,0 +1,290 @@
+use crate::http_client::{HttpClientConfig, HttpVersion};
+use crate::models::TlsSession;
+use reqwest::Url;
+use rustls::client::{ServerCertVerified, ServerCertVerifier};
+use rustls::{Certificate, ClientConfig, ClientConnection, OwnedTrustAnchor, PrivateKey, ProtocolVersion, RootCertStore, ServerName};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufReader;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::SystemTime;
+use tracing::warn;
+
+/// Lowest TLS version the client will negotiate
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum TlsVersion {
+    #[default]
+    #[serde(rename = "1.2")]
+    Tls12,
+    #[serde(rename = "1.3")]
+    Tls13,
+}
+
+impl TlsVersion {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "1.2" => Some(TlsVersion::Tls12),
+            "1.3" => Some(TlsVersion::Tls13),
+            _ => None,
+        }
+    }
+}
+
+/// Certificate chain and private key presented to servers that require mutual TLS, both PEM files
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ClientIdentity {
+    pub certificate: PathBuf,
+    pub key: PathBuf, // PKCS#8, PKCS#1 (RSA) or SEC1 (EC)
+}
+
+/// TLS settings for a client; requests always go through rustls with these settings. The
+/// session recorded per endpoint comes from a separate probe handshake with the same settings,
+/// made directly (not through a proxy or from a source address) before the endpoint's requests.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TlsConfig {
+    pub ca_certificates: Vec<PathBuf>, // PEM files trusted in addition to the system store and the built-in Mozilla roots
+    pub client_identity: Option<ClientIdentity>,
+    pub insecure: bool,              // Accept any server certificate; only for self-signed local targets
+    pub min_version: TlsVersion,
+    pub server_name: Option<String>, // Sent as SNI and Host instead of the URL's host, which is still where requests connect
+}
+
+impl TlsConfig {
+    /// rustls configuration for these settings, offering ALPN protocols to match `http_version`
+    pub fn client_config(&self, http_version: HttpVersion) -> Result<ClientConfig, Box<dyn std::error::Error>> {
+        let roots = self.root_store()?;
+        let versions: &[&rustls::SupportedProtocolVersion] = match self.min_version {
+            TlsVersion::Tls12 => &[&rustls::version::TLS13, &rustls::version::TLS12],
+            TlsVersion::Tls13 => &[&rustls::version::TLS13],
+        };
+        let builder = ClientConfig::builder()
+            .with_safe_default_cipher_suites()
+            .with_safe_default_kx_groups()
+            .with_protocol_versions(versions)?
+            .with_root_certificates(roots);
+
+        let mut config = match &self.client_identity {
+            Some(identity) => builder.with_client_auth_cert(
+                read_certificates(&identity.certificate)?,
+                read_private_key(&identity.key)?,
+            )?,
+            None => builder.with_no_client_auth(),
+        };
+        if self.insecure {
+            config.dangerous().set_certificate_verifier(Arc::new(AcceptAnyCertificate));
+        }
+        config.alpn_protocols = match http_version {
+            HttpVersion::Auto => vec![b"h2".to_vec(), b"http/1.1".to_vec()],
+            HttpVersion::Http1 => vec![b"http/1.1".to_vec()],
+            HttpVersion::Http2PriorKnowledge => vec![b"h2".to_vec()],
+        };
+        Ok(config)
+    }
+
+    /// Built-in Mozilla roots, the system trust store and `ca_certificates`
+    fn root_store(&self) -> Result<RootCertStore, Box<dyn std::error::Error>> {
+        let mut roots = RootCertStore::empty();
+        roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+            OwnedTrustAnchor::from_subject_spki_name_constraints(anchor.subject, anchor.spki, anchor.name_constraints)
+        }));
+        roots.add_parsable_certificates(native_roots());
+        for path in &self.ca_certificates {
+            for certificate in read_certificates(path)? {
+                roots.add(&certificate)
+                    .map_err(|e| format!("invalid CA certificate in {}: {}", path.display(), e))?;
+            }
+        }
+        Ok(roots)
+    }
+
+    /// `url` with its host replaced by `server_name`, when one is set
+    pub fn request_url(&self, url: &str) -> String {
+        if let (Some(server_name), Ok(mut parsed)) = (&self.server_name, Url::parse(url))
+            && parsed.set_host(Some(server_name)).is_ok()
+        {
+            return parsed.to_string();
+        }
+        url.to_string()
+    }
+
+}
+
+/// Handshake once with `url`'s host (its first `resolve` address, if any) using `config`'s TLS settings
+/// and report the session negotiated. The probe connects directly, ignoring `proxy` and `source_addresses`.
+pub(crate) fn negotiate_session(config: &HttpClientConfig, url: &str) -> Result<TlsSession, Box<dyn std::error::Error>> {
+    let (host, port) = host_and_port(url)?;
+    let server_name = ServerName::try_from(config.tls.server_name.as_deref().unwrap_or(&host))?;
+    let tls_config = config.tls.client_config(config.http_version)?;
+
+    let addr = match config.target_addresses(url).first() {
+        Some(address) => SocketAddr::new(*address, port),
+        None => (host.as_str(), port).to_socket_addrs()?
+            .next()
+            .ok_or_else(|| format!("no addresses for {}", host))?,
+    };
+    let mut socket = TcpStream::connect_timeout(&addr, config.connect_timeout)?;
+    socket.set_read_timeout(Some(config.request_timeout))?;
+    socket.set_write_timeout(Some(config.request_timeout))?;
+
+    let mut connection = ClientConnection::new(Arc::new(tls_config), server_name)?;
+    while connection.is_handshaking() {
+        connection.complete_io(&mut socket)?;
+    }
+    connection.send_close_notify();
+    let _ = connection.complete_io(&mut socket);
+
+    let version = match connection.protocol_version() {
+        Some(ProtocolVersion::TLSv1_2) => "TLS 1.2".to_string(),
+        Some(ProtocolVersion::TLSv1_3) => "TLS 1.3".to_string(),
+        Some(other) => format!("{:?}", other),
+        None => "unknown".to_string(),
+    };
+    let cipher_suite = connection.negotiated_cipher_suite()
+        .map(|suite| format!("{:?}", suite.suite()))
+        .unwrap_or_else(|| "unknown".to_string());
+    Ok(TlsSession { version, cipher_suite })
+}
+
+/// Addresses of `url`'s host, which `server_name` is pointed at so requests still connect there
+pub(crate) fn resolve_host(url: &str) -> Result<Vec<SocketAddr>, Box<dyn std::error::Error>> {
+    let (host, port) = host_and_port(url)?;
+    Ok((host.as_str(), port).to_socket_addrs()?.collect())
+}
+
+pub(crate) fn host_and_port(url: &str) -> Result<(String, u16), Box<dyn std::error::Error>> {
+    let parsed = Url::parse(url)?;
+    let host = parsed.host_str().ok_or_else(|| format!("no host in {}", url))?;
+    // IPv6 hosts come back bracketed
+    let host = host.trim_start_matches('[').trim_end_matches(']').to_string();
+    let port = parsed.port_or_known_default().ok_or_else(|| format!("no port for {}", url))?;
+    Ok((host, port))
+}
+
+/// Certificates in the operating system's trust store, loaded once, so internal CAs installed
+/// there stay trusted without a `ca_certificates` entry
+fn native_roots() -> &'static [rustls_native_certs::Certificate] {
+    static ROOTS: OnceLock<Vec<rustls_native_certs::Certificate>> = OnceLock::new();
+    ROOTS.get_or_init(|| {
+        rustls_native_certs::load_native_certs().unwrap_or_else(|e| {
+            warn!(error = %e, "Error loading the system trust store; trusting only the built-in roots");
+            Vec::new()
+        })
+    })
+}
+
+fn read_certificates(path: &Path) -> Result<Vec<Certificate>, Box<dyn std::error::Error>> {
+    let mut reader = BufReader::new(File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?);
+    let certificates: Vec<Certificate> = rustls_pemfile::certs(&mut reader)?.into_iter().map(Certificate).collect();
+    if certificates.is_empty() {
+        return Err(format!("no PEM certificates in {}", path.display()).into());
+    }
+    Ok(certificates)
+}
+
+fn read_private_key(path: &Path) -> Result<PrivateKey, Box<dyn std::error::Error>> {
+    let mut reader = BufReader::new(File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?);
+    for item in rustls_pemfile::read_all(&mut reader)? {
+        match item {
+            rustls_pemfile::Item::PKCS8Key(key) | rustls_pemfile::Item::RSAKey(key) | rustls_pemfile::Item::ECKey(key) => {
+                return Ok(PrivateKey(key));
+            }
+            _ => {}
+        }
+    }
+    Err(format!("no PEM private key in {}", path.display()).into())
+}
+
+/// Verifier for `insecure` mode: every certificate is accepted, signatures are still checked
+struct AcceptAnyCertificate;
+
+impl ServerCertVerifier for AcceptAnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn versions_parse_and_serialize_as_numbers() {
+        assert_eq!(TlsVersion::parse("1.3"), Some(TlsVersion::Tls13));
+        assert_eq!(TlsVersion::parse("1.1"), None);
+        assert_eq!(serde_json::to_string(&TlsVersion::Tls12).unwrap(), "\"1.2\"");
+        assert!(TlsVersion::Tls12 < TlsVersion::Tls13);
+    }
+
+    #[test]
+    fn request_url_swaps_in_the_server_name() {
+        let mut config = TlsConfig::default();
+        assert_eq!(config.request_url("https://10.0.0.1:8443/api?q=1"), "https://10.0.0.1:8443/api?q=1");
+        config.server_name = Some("api.example.com".to_string());
+        assert_eq!(config.request_url("https://10.0.0.1:8443/api?q=1"), "https://api.example.com:8443/api?q=1");
+        assert_eq!(config.request_url("not a url"), "not a url");
+    }
+
+    #[test]
+    fn host_and_port_fill_in_defaults() {
+        assert_eq!(host_and_port("https://api.test/x").unwrap(), ("api.test".to_string(), 443));
+        assert_eq!(host_and_port("ws://api.test/x").unwrap(), ("api.test".to_string(), 80));
+        assert_eq!(host_and_port("http://[::1]:8080/").unwrap(), ("::1".to_string(), 8080));
+        assert!(host_and_port("/relative").is_err());
+    }
+
+    #[test]
+    fn client_config_offers_alpn_for_the_http_version() {
+        let alpn = |config: &TlsConfig, version| config.client_config(version).unwrap().alpn_protocols;
+        let config = TlsConfig::default();
+        assert_eq!(alpn(&config, HttpVersion::Auto), vec![b"h2".to_vec(), b"http/1.1".to_vec()]);
+        assert_eq!(alpn(&config, HttpVersion::Http1), vec![b"http/1.1".to_vec()]);
+        let strict = TlsConfig { min_version: TlsVersion::Tls13, insecure: true, ..TlsConfig::default() };
+        assert_eq!(alpn(&strict, HttpVersion::Http2PriorKnowledge), vec![b"h2".to_vec()]);
+    }
+
+    #[test]
+    fn default_roots_include_the_system_store() {
+        let mut native = RootCertStore::empty();
+        native.add_parsable_certificates(native_roots());
+        assert!(!native.is_empty(), "no system trust store to test against");
+        let roots = TlsConfig::default().root_store().unwrap();
+        assert_eq!(roots.len(), webpki_roots::TLS_SERVER_ROOTS.len() + native.len());
+    }
+
+    #[test]
+    fn unreadable_certificate_files_are_reported_by_path() {
+        let dir = std::env::temp_dir().join(format!("load-test-rs-tls-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let empty = dir.join("empty.pem");
+        fs::write(&empty, "not a certificate\n").unwrap();
+
+        let missing = TlsConfig { ca_certificates: vec![dir.join("missing.pem")], ..TlsConfig::default() };
+        let no_certificates = TlsConfig { ca_certificates: vec![empty.clone()], ..TlsConfig::default() };
+        let no_key = TlsConfig {
+            client_identity: Some(ClientIdentity { certificate: empty.clone(), key: empty.clone() }),
+            ..TlsConfig::default()
+        };
+        let missing_error = missing.client_config(HttpVersion::Auto).unwrap_err().to_string();
+        let empty_error = no_certificates.client_config(HttpVersion::Auto).unwrap_err().to_string();
+        let key_error = read_private_key(&empty).unwrap_err().to_string();
+        let identity_error = no_key.client_config(HttpVersion::Auto).unwrap_err().to_string();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(missing_error.contains("missing.pem"));
+        assert_eq!(empty_error, format!("no PEM certificates in {}", empty.display()));
+        assert_eq!(key_error, format!("no PEM private key in {}", empty.display()));
+        assert_eq!(identity_error, empty_error);
+    }
+}
@@ -0,0 +1,104 @@
+use crate::models::SlowRequest;
+
+pub const DEFAULT_SLOWEST_REQUESTS: usize = 5;
+
+/// IDs sent with one request so it can be found in a tracing backend
+#[derive(Clone, Debug)]
+pub struct TraceContext {
+    pub trace_id: String, // 32 lowercase hex digits
+    pub span_id: String,  // 16 lowercase hex digits
+    pub request_id: Option<String>,
+}
+
+impl TraceContext {
+    /// Fresh random IDs; `request_id` is a UUIDv4 when requested
+    pub fn generate(with_request_id: bool) -> Self {
+        // All-zero trace and span IDs are invalid
+        let trace_id = rand::random::<u128>().max(1);
+        let span_id = rand::random::<u64>().max(1);
+        let request_id = with_request_id.then(|| {
+            let bits = rand::random::<u128>();
+            // Set the version (4) and variant (10xx) bits
+            let uuid = (bits & !(0xf << 76) & !(0x3 << 62)) | (0x4 << 76) | (0x2 << 62);
+            let hex = format!("{:032x}", uuid);
+            format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+        });
+
+        Self {
+            trace_id: format!("{:032x}", trace_id),
+            span_id: format!("{:016x}", span_id),
+            request_id,
+        }
+    }
+
+    /// W3C Trace Context `traceparent` value, always sampled
+    pub fn traceparent(&self) -> String {
+        format!("00-{}-{}-01", self.trace_id, self.span_id)
+    }
+}
+
+/// Insert `request` into `slowest`, keeping the `limit` slowest sorted slowest first
+pub(crate) fn keep_slowest(slowest: &mut Vec<SlowRequest>, request: SlowRequest, limit: usize) {
+    if limit == 0 || (slowest.len() >= limit && slowest.last().is_some_and(|r| r.latency_ms >= request.latency_ms)) {
+        return;
+    }
+    let position = slowest.partition_point(|r| r.latency_ms >= request.latency_ms);
+    slowest.insert(position, request);
+    slowest.truncate(limit);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+
+    fn is_lower_hex(text: &str) -> bool {
+        text.chars().all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+    }
+
+    fn slow(latency_ms: u64) -> SlowRequest {
+        SlowRequest { sent_at: Local::now(), latency_ms, status: Some(200), trace_id: None, request_id: None }
+    }
+
+    #[test]
+    fn traceparent_is_w3c_formatted() {
+        let context = TraceContext::generate(false);
+        let traceparent = context.traceparent();
+        let parts: Vec<&str> = traceparent.split('-').collect();
+        assert_eq!(parts.len(), 4);
+        assert_eq!((parts[0], parts[3]), ("00", "01"));
+        assert_eq!((parts[1].len(), parts[2].len()), (32, 16));
+        assert!(is_lower_hex(parts[1]) && is_lower_hex(parts[2]));
+        assert_ne!(parts[1], "0".repeat(32));
+        assert_ne!(parts[2], "0".repeat(16));
+        assert!(context.request_id.is_none());
+    }
+
+    #[test]
+    fn request_id_is_a_uuid_v4() {
+        for _ in 0..100 {
+            let request_id = TraceContext::generate(true).request_id.unwrap();
+            let groups: Vec<&str> = request_id.split('-').collect();
+            assert_eq!(groups.iter().map(|group| group.len()).collect::<Vec<_>>(), vec![8, 4, 4, 4, 12]);
+            assert!(groups.iter().all(|group| is_lower_hex(group)));
+            assert!(groups[2].starts_with('4'));
+            assert!(matches!(groups[3].as_bytes()[0], b'8' | b'9' | b'a' | b'b'));
+        }
+    }
+
+    #[test]
+    fn keep_slowest_keeps_the_slowest_sorted() {
+        let mut slowest = Vec::new();
+        for latency in [30, 10, 50, 20, 40, 50] {
+            keep_slowest(&mut slowest, slow(latency), 3);
+        }
+        assert_eq!(slowest.iter().map(|r| r.latency_ms).collect::<Vec<_>>(), vec![50, 50, 40]);
+
+        keep_slowest(&mut slowest, slow(40), 3);
+        assert_eq!(slowest.iter().map(|r| r.latency_ms).collect::<Vec<_>>(), vec![50, 50, 40]);
+
+        let mut none = Vec::new();
+        keep_slowest(&mut none, slow(99), 0);
+        assert!(none.is_empty());
+    }
+}
@@ -0,0 +1,377 @@
+use crate::http_client::{HttpClientConfig, HttpVersion};
+use crate::live::RunControl;
+use crate::models::ErrorClass;
+use crate::tls::host_and_port;
+use minijinja::{Environment, context};
+use reqwest::header::HeaderValue;
+use std::io::ErrorKind;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tungstenite::client::IntoClientRequest;
+use tungstenite::handshake::HandshakeError;
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Connector, Message, WebSocket};
+
+/// A WebSocket endpoint driven in every scenario after the HTTP endpoints.
+///
+/// Each connection sends its script in lock-step: a message, then the next text or binary message
+/// from the server is taken as its reply. Servers that push unrelated messages skew the round trips.
+#[derive(Clone, Debug)]
+pub struct WebSocketScenario {
+    pub path: String,               // Appended to the base URL, whose http/https scheme becomes ws/wss
+    pub connections: Option<usize>, // Held open together; defaults to the scenario's concurrency
+    pub messages: Vec<String>,      // minijinja templates sent in turn; see `MessageScript`
+    pub messages_per_connection: usize,
+    pub message_rate: f64, // Messages per second on each connection
+}
+
+impl WebSocketScenario {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            connections: None,
+            messages: vec!["ping".to_string()],
+            messages_per_connection: 10,
+            message_rate: 1.0,
+        }
+    }
+
+    /// Name of the scenario's row among the endpoint results, e.g. "WS /chat"
+    pub fn endpoint_name(&self) -> String {
+        format!("WS {}", self.path)
+    }
+
+    /// `base_url` plus `path`, with the matching WebSocket scheme
+    pub fn url(&self, base_url: &str) -> String {
+        let url = format!("{}{}", base_url, self.path);
+        if let Some(rest) = url.strip_prefix("https://") {
+            format!("wss://{}", rest)
+        } else if let Some(rest) = url.strip_prefix("http://") {
+            format!("ws://{}", rest)
+        } else {
+            url
+        }
+    }
+}
+
+/// What happened on a connection, reported as it happens
+pub(crate) enum WebSocketEvent {
+    Connected { latency_ms: u64 },
+    ConnectFailed { latency_ms: u64, status: Option<u16>, error_class: ErrorClass, error: String },
+    Reply { latency_ms: u64 },
+    NoReply { latency_ms: u64, error_class: ErrorClass, error: String }, // The connection stays open after a timeout
+    RenderFailed { error: String }, // The message's template failed to render, so nothing was sent
+    Disconnected, // Closed by the server or dropped before the script finished
+}
+
+/// A scenario's messages, rendered per send with `connection` (0-based), `sequence` (0-based,
+/// per connection) and `user_id`
+struct MessageScript {
+    env: Environment<'static>,
+    count: usize,
+}
+
+impl MessageScript {
+    fn new(messages: &[String]) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut env = Environment::new();
+        for (index, message) in messages.iter().enumerate() {
+            env.add_template_owned(index.to_string(), message.clone())?;
+        }
+        Ok(Self { env, count: messages.len() })
+    }
+
+    fn render(&self, connection: usize, sequence: usize, user_id: &str) -> Result<String, minijinja::Error> {
+        self.env
+            .get_template(&(sequence % self.count).to_string())?
+            .render(context! { connection, sequence, user_id })
+    }
+}
+
+/// Opens the connections of one `WebSocketScenario`, sharing its settings between connection threads.
+///
+/// Connections go directly to the host, or its `resolve` addresses, with the client's timeouts and TLS
+/// settings; `proxy` and `source_addresses` are not applied.
+pub(crate) struct WebSocketClient<'a> {
+    scenario: &'a WebSocketScenario,
+    config: &'a HttpClientConfig,
+    url: String,         // Where connections go
+    request_url: String, // Sent in the handshake, with `server_name` as its host
+    tls: Option<Arc<rustls::ClientConfig>>,
+    headers: Vec<(&'static str, String)>,
+    user_id: String,
+    script: MessageScript,
+}
+
+impl<'a> WebSocketClient<'a> {
+    pub(crate) fn new(
+        scenario: &'a WebSocketScenario,
+        config: &'a HttpClientConfig,
+        base_url: &str,
+        headers: Vec<(&'static str, String)>,
+        user_id: &str,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        if scenario.messages.is_empty() {
+            return Err(format!("no messages for WebSocket {}", scenario.path).into());
+        }
+        let url = scenario.url(base_url);
+        let tls = if url.starts_with("wss://") {
+            // The upgrade is an HTTP/1.1 request, whatever version the HTTP endpoints use
+            Some(Arc::new(config.tls.client_config(HttpVersion::Http1)?))
+        } else {
+            None
+        };
+        Ok(Self {
+            scenario,
+            config,
+            request_url: config.tls.request_url(&url),
+            url,
+            tls,
+            headers,
+            user_id: user_id.to_string(),
+            script: MessageScript::new(&scenario.messages)?,
+        })
+    }
+
+    /// Connect, run the script at `message_rate` and close, reporting each step to `on_event`
+    pub(crate) fn run_connection(
+        &self,
+        connection: usize,
+        cookie: Option<HeaderValue>,
+        control: &RunControl,
+        on_event: &mut dyn FnMut(WebSocketEvent),
+    ) {
+        let connect_start = Instant::now();
+        let mut socket = match self.connect(connection, cookie) {
+            Ok(socket) => socket,
+            Err((status, error_class, error)) => {
+                let latency_ms = connect_start.elapsed().as_millis() as u64;
+                on_event(WebSocketEvent::ConnectFailed { latency_ms, status, error_class, error });
+                return;
+            }
+        };
+        let connected_at = Instant::now();
+        on_event(WebSocketEvent::Connected { latency_ms: connected_at.duration_since(connect_start).as_millis() as u64 });
+
+        for sequence in 0..self.scenario.messages_per_connection {
+            while control.is_paused() && !control.should_stop_scenario() {
+                thread::sleep(Duration::from_millis(50));
+            }
+            if control.should_stop_scenario() {
+                break;
+            }
+            // A send time too far out to represent can't come before the run ends
+            let Some(due) = send_offset(sequence, self.scenario.message_rate)
+                .and_then(|offset| connected_at.checked_add(offset))
+            else {
+                break;
+            };
+            if let Some(wait) = due.checked_duration_since(Instant::now()) {
+                thread::sleep(wait);
+            }
+
+            let text = match self.script.render(connection, sequence, &self.user_id) {
+                Ok(text) => text,
+                Err(e) => {
+                    on_event(WebSocketEvent::RenderFailed { error: e.to_string() });
+                    continue;
+                }
+            };
+            let sent_at = Instant::now();
+            if let Err(e) = socket.send(Message::Text(text)) {
+                let latency_ms = sent_at.elapsed().as_millis() as u64;
+                on_event(WebSocketEvent::NoReply { latency_ms, error_class: ErrorClass::Other, error: e.to_string() });
+                on_event(WebSocketEvent::Disconnected);
+                return;
+            }
+            match read_reply(&mut socket) {
+                Ok(()) => on_event(WebSocketEvent::Reply { latency_ms: sent_at.elapsed().as_millis() as u64 }),
+                Err(Reply::TimedOut(error)) => {
+                    let latency_ms = sent_at.elapsed().as_millis() as u64;
+                    on_event(WebSocketEvent::NoReply { latency_ms, error_class: ErrorClass::Timeout, error });
+                }
+                Err(Reply::Closed(error)) => {
+                    let latency_ms = sent_at.elapsed().as_millis() as u64;
+                    on_event(WebSocketEvent::NoReply { latency_ms, error_class: ErrorClass::Other, error });
+                    on_event(WebSocketEvent::Disconnected);
+                    return;
+                }
+            }
+        }
+
+        // Finish the closing handshake so the server sees a clean close rather than a dropped connection
+        if socket.close(None).is_ok() {
+            while socket.read().is_ok() {}
+        }
+    }
+
+    fn connect(
+        &self,
+        connection: usize,
+        cookie: Option<HeaderValue>,
+    ) -> Result<WebSocket<MaybeTlsStream<TcpStream>>, (Option<u16>, ErrorClass, String)> {
+        let other = |e: &dyn std::fmt::Display| (None, ErrorClass::Other, e.to_string());
+        let stream = self.connect_tcp(connection).map_err(|e| {
+            let error_class = if e.kind() == ErrorKind::TimedOut { ErrorClass::Timeout } else { ErrorClass::Connect };
+            (None, error_class, e.to_string())
+        })?;
+
+        let mut request = self.request_url.as_str().into_client_request().map_err(|e| other(&e))?;
+        for (name, value) in &self.headers {
+            request.headers_mut().insert(*name, value.parse().map_err(|e| other(&e))?);
+        }
+        if let Some(cookie) = cookie {
+            let cookie = cookie.to_str().map_err(|e| other(&e))?.parse().map_err(|e| other(&e))?;
+            request.headers_mut().insert("cookie", cookie);
+        }
+
+        let connector = match &self.tls {
+            Some(tls) => Connector::Rustls(Arc::clone(tls)),
+            None => Connector::Plain,
+        };
+        match tungstenite::client_tls_with_config(request, stream, None, Some(connector)) {
+            Ok((socket, _)) => Ok(socket),
+            Err(HandshakeError::Failure(tungstenite::Error::Http(response))) => {
+                let status = response.status().as_u16();
+                let error_class = ErrorClass::from_status(status).unwrap_or(ErrorClass::Other);
+                Err((Some(status), error_class, format!("HTTP {}", response.status())))
+            }
+            Err(HandshakeError::Failure(tungstenite::Error::Io(e))) if is_timeout(&e) => {
+                Err((None, ErrorClass::Timeout, e.to_string()))
+            }
+            Err(HandshakeError::Interrupted(_)) => Err((None, ErrorClass::Timeout, "handshake timed out".to_string())),
+            Err(HandshakeError::Failure(e)) => Err(other(&e)),
+        }
+    }
+
+    /// TCP connection to the URL's host, or to the `resolve` address picked for `connection`
+    fn connect_tcp(&self, connection: usize) -> std::io::Result<TcpStream> {
+        let (host, port) = host_and_port(&self.url).map_err(|e| std::io::Error::new(ErrorKind::InvalidInput, e.to_string()))?;
+        let targets = self.config.target_addresses(&self.url);
+        let addresses: Vec<SocketAddr> = if targets.is_empty() {
+            (host.as_str(), port).to_socket_addrs()?.collect()
+        } else {
+            vec![SocketAddr::new(targets[self.config.address_selection.pick(connection, targets.len())], port)]
+        };
+
+        let mut last_error = std::io::Error::new(ErrorKind::NotFound, format!("no addresses for {}", host));
+        for address in addresses {
+            match TcpStream::connect_timeout(&address, self.config.connect_timeout) {
+                Ok(stream) => {
+                    stream.set_read_timeout(Some(self.config.request_timeout))?;
+                    stream.set_write_timeout(Some(self.config.request_timeout))?;
+                    stream.set_nodelay(true)?;
+                    return Ok(stream);
+                }
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    }
+}
+
+enum Reply {
+    TimedOut(String),
+    Closed(String),
+}
+
+/// Wait for the next text or binary message; control frames are answered by tungstenite and skipped
+fn read_reply(socket: &mut WebSocket<MaybeTlsStream<TcpStream>>) -> Result<(), Reply> {
+    loop {
+        match socket.read() {
+            Ok(Message::Text(_)) | Ok(Message::Binary(_)) => return Ok(()),
+            Ok(Message::Close(frame)) => {
+                let reason = frame.map(|frame| format!("closed by server: {} {}", frame.code, frame.reason));
+                return Err(Reply::Closed(reason.unwrap_or_else(|| "closed by server".to_string())));
+            }
+            Ok(_) => continue,
+            Err(tungstenite::Error::Io(e)) if is_timeout(&e) => return Err(Reply::TimedOut(e.to_string())),
+            Err(e) => return Err(Reply::Closed(e.to_string())),
+        }
+    }
+}
+
+/// Socket timeouts surface as `WouldBlock` on Unix and `TimedOut` on Windows
+fn is_timeout(e: &std::io::Error) -> bool {
+    matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut)
+}
+
+/// When the `sequence`-th message is due after the connection opened, at `message_rate` per second
+/// (back to back when the rate isn't positive); `None` when that is too far out to represent
+fn send_offset(sequence: usize, message_rate: f64) -> Option<Duration> {
+    if message_rate > 0.0 {
+        Duration::try_from_secs_f64(sequence as f64 / message_rate).ok()
+    } else {
+        Some(Duration::ZERO)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn url_swaps_the_scheme() {
+        let scenario = WebSocketScenario::new("/chat");
+        assert_eq!(scenario.url("http://api.test"), "ws://api.test/chat");
+        assert_eq!(scenario.url("https://api.test:8443"), "wss://api.test:8443/chat");
+        assert_eq!(scenario.endpoint_name(), "WS /chat");
+    }
+
+    #[test]
+    fn script_cycles_through_messages() {
+        let messages = ["hello {{ user_id }}".to_string(), "#{{ connection }}.{{ sequence }}".to_string()];
+        let script = MessageScript::new(&messages).unwrap();
+        assert_eq!(script.render(2, 0, "u1").unwrap(), "hello u1");
+        assert_eq!(script.render(2, 1, "u1").unwrap(), "#2.1");
+        assert_eq!(script.render(2, 2, "u1").unwrap(), "hello u1");
+    }
+
+    #[test]
+    fn send_offset_never_overflows() {
+        assert_eq!(send_offset(0, 2.0), Some(Duration::ZERO));
+        assert_eq!(send_offset(5, 2.0), Some(Duration::from_millis(2500)));
+        assert_eq!(send_offset(5, 0.0), Some(Duration::ZERO));
+        // Past u32::MAX messages, and at rates whose offsets don't fit in a Duration
+        let past_u32 = u32::MAX as usize + 2;
+        assert_eq!(send_offset(past_u32, 1000.0), Some(Duration::from_secs_f64(past_u32 as f64 / 1000.0)));
+        assert_eq!(send_offset(usize::MAX, 1e-300), None);
+    }
+
+    #[test]
+    fn render_failures_are_reported_without_a_latency() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut socket = tungstenite::accept(stream).unwrap();
+            while let Ok(message) = socket.read() {
+                if message.is_text() && socket.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut scenario = WebSocketScenario::new("/echo");
+        // Every other message calls a function that doesn't exist
+        scenario.messages = vec!["ping {{ sequence }}".to_string(), "{{ missing() }}".to_string()];
+        scenario.messages_per_connection = 4;
+        scenario.message_rate = 0.0;
+        let config = HttpClientConfig::default();
+        let base_url = format!("http://127.0.0.1:{}", port);
+        let client = WebSocketClient::new(&scenario, &config, &base_url, Vec::new(), "u1").unwrap();
+
+        let (mut replies, mut render_failures, mut no_replies) = (0, 0, 0);
+        client.run_connection(0, None, &RunControl::default(), &mut |event| match event {
+            WebSocketEvent::Reply { .. } => replies += 1,
+            WebSocketEvent::RenderFailed { .. } => render_failures += 1,
+            WebSocketEvent::NoReply { .. } => no_replies += 1,
+            _ => {}
+        });
+        server.join().unwrap();
+
+        assert_eq!((replies, render_failures, no_replies), (2, 2, 0));
+    }
+}